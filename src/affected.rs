@@ -0,0 +1,160 @@
+//! Project roots affected by a set of changed paths.
+//!
+//! Changed paths go through [`find_root_infos_batch`] like any other batch.
+//! Paths that no longer exist (deleted files, possibly in deleted directories)
+//! are laid over [`Config::filesystem`] as empty files, so they get the root
+//! they would have had: the nearest marker directory that still exists, or
+//! their orphanage.
+
+use crate::{find_root_infos_batch, Config, FileStamp, FileSystem};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Deduplicated, sorted roots of the changed `paths`; excluded paths affect
+/// no root.
+#[must_use]
+pub fn affected_roots<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    config: &Config,
+) -> Vec<PathBuf> {
+    let paths: Vec<&'a Path> = paths.into_iter().collect();
+    let overlay = DeletedPaths::new(Arc::clone(&config.filesystem), &paths);
+    let config = config.clone().with_filesystem(overlay);
+
+    let roots: BTreeSet<PathBuf> = find_root_infos_batch(paths, &config)
+        .into_iter()
+        .filter_map(|(_, info)| info.map(|i| i.root))
+        .collect();
+    roots.into_iter().collect()
+}
+
+/// Missing paths shown as empty files on top of another filesystem
+#[derive(Debug)]
+struct DeletedPaths {
+    inner: Arc<dyn FileSystem>,
+    files: HashSet<PathBuf>,
+    /// Missing directories and missing entries of any directory
+    dirs: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl DeletedPaths {
+    fn new(inner: Arc<dyn FileSystem>, paths: &[&Path]) -> Self {
+        let mut files = HashSet::new();
+        let mut dirs: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for path in paths.iter().filter(|path| !inner.exists(path)) {
+            files.insert(path.to_path_buf());
+            let mut child = *path;
+            while let Some(parent) = child.parent() {
+                dirs.entry(parent.to_path_buf())
+                    .or_default()
+                    .insert(child.to_path_buf());
+                if inner.exists(parent) {
+                    break;
+                }
+                child = parent;
+            }
+        }
+        Self { inner, files, dirs }
+    }
+
+    fn is_missing(&self, path: &Path) -> bool {
+        self.files.contains(path) || (self.dirs.contains_key(path) && !self.inner.exists(path))
+    }
+}
+
+impl FileSystem for DeletedPaths {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let missing = self.dirs.get(dir);
+        let mut entries = match self.inner.read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) if missing.is_some() => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        entries.extend(missing.into_iter().flatten().cloned());
+        entries.sort();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path) || (self.dirs.contains_key(path) && !self.files.contains(path))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains(path) || self.inner.is_file(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if !self.is_missing(path) {
+            return self.inner.canonicalize(path);
+        }
+        // Resolve the part that still exists and keep the rest as given
+        let existing = path
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| !self.is_missing(ancestor))
+            .unwrap_or_else(|| Path::new(""));
+        let rest = path.strip_prefix(existing).unwrap_or(path);
+        let base = if existing.as_os_str().is_empty() {
+            self.inner.canonicalize(Path::new("."))?
+        } else {
+            self.inner.canonicalize(existing)?
+        };
+        Ok(base.join(rest))
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        if self.files.contains(path) {
+            return Ok(String::new());
+        }
+        self.inner.read_to_string(path)
+    }
+
+    fn stamp(&self, path: &Path) -> Option<FileStamp> {
+        if self.is_missing(path) {
+            return None;
+        }
+        self.inner.stamp(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_affected_roots_include_deleted_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for (path, contents) in [
+            ("api/Cargo.toml", "[package]\nname = \"api\"\n"),
+            ("api/src/lib.rs", ""),
+            ("web/package.json", r#"{"name": "web"}"#),
+            ("web/index.js", ""),
+            ("docs/guide.md", ""),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let changed = [
+            root.join("api/src/lib.rs"),
+            // Deleted, together with its directory
+            root.join("api/src/old/mod.rs"),
+            root.join("web/index.js"),
+            root.join("web/node_modules/dep/index.js"),
+        ];
+        let roots = affected_roots(changed.iter().map(PathBuf::as_path), &Config::default());
+        assert_eq!(roots, vec![root.join("api"), root.join("web")]);
+
+        // A deleted file outside any project is an orphan in its old directory
+        let changed = [root.join("tools/gen/run.py")];
+        let roots = affected_roots(changed.iter().map(PathBuf::as_path), &Config::default());
+        assert_eq!(roots, vec![root.join("tools/gen")]);
+    }
+}
@@ -0,0 +1,290 @@
+//! Detection inside `.tar`, `.tar.gz` / `.tgz` and `.zip` archives.
+//!
+//! The archive is loaded into a [`MemoryFs`] rooted at the empty path and
+//! traversed like a directory, so results use archive-internal paths
+//! (`pkg-1.0/src/lib.rs -> pkg-1.0`). Only small text files keep their
+//! contents, which is all content predicates, ignore files and workspace
+//! manifests need.
+
+use crate::{
+    traverse_and_detect, Config, MemoryFs, RootDetectionError, TraversalOptions, TraversalResult,
+};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+/// Larger files are kept as empty files: their contents never decide a root
+const MAX_CONTENT_BYTES: u64 = 1024 * 1024;
+
+/// Archive formats recognised by their leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn sniff(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if header.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Load a `.tar`, `.tar.gz` / `.tgz` or `.zip` archive into memory.
+///
+/// Entry paths are relative to the archive's top (leading `/` and `./` are
+/// dropped). Symlinks and other special entries are skipped.
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::ArchiveRead`] if the file cannot be read or
+/// is not a supported archive.
+pub fn load_archive(path: &Path) -> Result<MemoryFs, RootDetectionError> {
+    let read_error = |source| RootDetectionError::ArchiveRead {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let mut file = BufReader::new(File::open(path).map_err(read_error)?);
+    let mut header = [0u8; 512];
+    let len = read_up_to(&mut file, &mut header).map_err(read_error)?;
+    file.rewind().map_err(read_error)?;
+
+    let mut tree = MemoryFs::new();
+    match ArchiveFormat::sniff(&header[..len]) {
+        Some(ArchiveFormat::Tar) => load_tar(file, &mut tree),
+        Some(ArchiveFormat::TarGz) => load_tar(GzDecoder::new(file), &mut tree),
+        Some(ArchiveFormat::Zip) => load_zip(file, &mut tree),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a tar, tar.gz or zip archive",
+        )),
+    }
+    .map_err(read_error)?;
+    Ok(tree)
+}
+
+/// Detect project roots for every file in an archive, like
+/// [`traverse_and_detect`] over its extracted contents.
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::ArchiveRead`] if the archive cannot be loaded.
+pub fn traverse_archive(
+    archive: &Path,
+    config: &Config,
+    options: &TraversalOptions,
+) -> Result<Vec<TraversalResult>, RootDetectionError> {
+    let config = config.clone().with_filesystem(load_archive(archive)?);
+    Ok(traverse_and_detect(Path::new(""), &config, options))
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Archive entry name as a relative path, or `None` for the archive's top
+/// and for names with `..`, which could point outside the archive
+fn entry_path(name: &Path) -> Option<PathBuf> {
+    if name.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+    let relative: PathBuf = name
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Contents worth keeping: small UTF-8 files
+fn read_contents(reader: impl Read, size: u64) -> io::Result<String> {
+    if size > MAX_CONTENT_BYTES {
+        return Ok(String::new());
+    }
+    let mut bytes = Vec::new();
+    reader.take(MAX_CONTENT_BYTES).read_to_end(&mut bytes)?;
+    Ok(String::from_utf8(bytes).unwrap_or_default())
+}
+
+fn load_tar(reader: impl Read, tree: &mut MemoryFs) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let Some(path) = entry_path(&entry.path()?) else {
+            continue;
+        };
+        let kind = entry.header().entry_type();
+        if kind.is_dir() {
+            tree.add_dir(path);
+        } else if kind.is_file() {
+            let size = entry.size();
+            tree.add_file(path, read_contents(entry, size)?);
+        }
+    }
+    Ok(())
+}
+
+fn load_zip(reader: impl Read + Seek, tree: &mut MemoryFs) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        let Some(path) = entry.enclosed_name().and_then(entry_path) else {
+            continue;
+        };
+        if entry.is_dir() {
+            tree.add_dir(path);
+        } else if !entry.unix_mode().is_some_and(is_symlink_mode) {
+            let size = entry.size();
+            tree.add_file(path, read_contents(entry, size)?);
+        }
+    }
+    Ok(())
+}
+
+const fn is_symlink_mode(mode: u32) -> bool {
+    mode & 0o170_000 == 0o120_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystem;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const ENTRIES: &[(&str, &str)] = &[
+        ("release-1.0/Cargo.toml", "[package]\nname = \"core\"\n"),
+        ("release-1.0/src/lib.rs", ""),
+        ("release-1.0/web/package.json", r#"{"name": "web"}"#),
+        ("release-1.0/web/index.js", ""),
+        ("release-1.0/web/node_modules/dep/index.js", ""),
+        ("release-1.0/tools/gen.py", ""),
+    ];
+
+    fn write_tar(writer: impl Write) {
+        let mut builder = tar::Builder::new(writer);
+        for (name, contents) in ENTRIES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("./{name}"), contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap();
+    }
+
+    fn write_zip(path: &Path) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, contents) in ENTRIES {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn roots(results: &[TraversalResult]) -> Vec<(String, Option<String>)> {
+        results
+            .iter()
+            .map(|r| {
+                let display = |p: &Path| p.display().to_string();
+                (display(&r.file), r.root.as_deref().map(display))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_formats_give_the_same_roots() {
+        let temp = TempDir::new().unwrap();
+        let tar_path = temp.path().join("release.tar");
+        let tgz_path = temp.path().join("release.tgz");
+        let zip_path = temp.path().join("release.zip");
+        write_tar(File::create(&tar_path).unwrap());
+        write_tar(GzEncoder::new(
+            File::create(&tgz_path).unwrap(),
+            Compression::default(),
+        ));
+        write_zip(&zip_path);
+
+        let config = Config::default();
+        let options = TraversalOptions::default().with_extensions(&["rs", "js", "py"]);
+        let expected = vec![
+            (
+                "release-1.0/src/lib.rs".to_string(),
+                Some("release-1.0".to_string()),
+            ),
+            (
+                "release-1.0/tools/gen.py".to_string(),
+                Some("release-1.0".to_string()),
+            ),
+            (
+                "release-1.0/web/index.js".to_string(),
+                Some("release-1.0/web".to_string()),
+            ),
+        ];
+        for archive in [&tar_path, &tgz_path, &zip_path] {
+            let results = traverse_archive(archive, &config, &options).unwrap();
+            assert_eq!(roots(&results), expected, "{}", archive.display());
+        }
+
+        // Contents are kept for predicates
+        let tree = load_archive(&zip_path).unwrap();
+        assert!(tree
+            .read_to_string(Path::new("release-1.0/web/package.json"))
+            .unwrap()
+            .contains("web"));
+    }
+
+    #[test]
+    fn test_parent_dir_entries_are_dropped() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for name in ["app/main.rs", "../escape.rs", "app/../../up.rs"] {
+            // `Header::set_path` refuses `..`, so write the raw name
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(0);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, io::empty()).unwrap();
+        }
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("evil.tar");
+        std::fs::write(&path, builder.into_inner().unwrap()).unwrap();
+
+        let results =
+            traverse_archive(&path, &Config::default(), &TraversalOptions::default()).unwrap();
+        let files: Vec<&Path> = results.iter().map(|r| r.file.as_path()).collect();
+        assert_eq!(files, vec![Path::new("app/main.rs")]);
+    }
+
+    #[test]
+    fn test_unsupported_archive_is_error() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("notes.txt");
+        std::fs::write(&path, "not an archive").unwrap();
+        assert!(matches!(
+            load_archive(&path),
+            Err(RootDetectionError::ArchiveRead { .. })
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+//! Bazel, Buck and Pants repositories.
+//!
+//! With [`crate::RootStrategy::BuildSystem`] the root is the closest directory
+//! holding a repository root file, and each file also reports its build
+//! package: the nearest directory below that root with a BUILD file.
+//!
+//! ```text
+//! repo/
+//! ├── MODULE.bazel          ← repository root
+//! └── src/
+//!     └── net/
+//!         ├── BUILD.bazel   ← package //src/net
+//!         └── http/conn.cc  → root: repo/, package: repo/src/net (//src/net)
+//! ```
+
+use crate::FileSystem;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Files marking the root of a Bazel, Buck or Pants repository
+pub const BUILD_SYSTEM_ROOT_MARKERS: &[&str] = &[
+    "MODULE.bazel",
+    "WORKSPACE.bazel",
+    "WORKSPACE",
+    "REPO.bazel",
+    ".buckconfig",
+    "pants.toml",
+];
+
+/// Files that make a directory a build package
+pub const BUILD_FILES: &[&str] = &["BUILD.bazel", "BUILD", "BUCK", "TARGETS"];
+
+/// Build package enclosing a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildPackage {
+    /// Directory holding the BUILD file
+    pub dir: PathBuf,
+    /// Label-style package path relative to the repository root (`//src/net`)
+    pub label: String,
+}
+
+/// Nearest build package of `source` within the repository at `root`, or
+/// `None` if no BUILD file lies between them
+pub fn find_build_package(source: &Path, root: &Path, fs: &dyn FileSystem) -> Option<BuildPackage> {
+    let dir = source
+        .parent()?
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| BUILD_FILES.iter().any(|name| fs.is_file(&dir.join(name))))?;
+
+    let segments: Vec<&str> = dir
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    Some(BuildPackage {
+        dir: dir.to_path_buf(),
+        label: format!("//{}", segments.join("/")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_root_info, Config, DecisionReason, MemoryFs, RootStrategy};
+    use std::collections::HashSet;
+
+    fn info(fs: MemoryFs, file: &str) -> crate::RootInfo {
+        let config = Config::default()
+            .with_filesystem(fs)
+            .with_strategy(RootStrategy::BuildSystem);
+        find_root_info(
+            Path::new(file),
+            None::<&HashSet<PathBuf>>,
+            None::<&HashSet<PathBuf>>,
+            &config,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bazel_root_and_package() {
+        let mut fs = MemoryFs::new();
+        for path in [
+            "/repo/.git/HEAD",
+            "/repo/MODULE.bazel",
+            "/repo/BUILD.bazel",
+            "/repo/src/net/BUILD.bazel",
+            "/repo/src/net/http/conn.cc",
+            "/repo/src/net/Cargo.toml",
+            "/repo/tools/gen.py",
+        ] {
+            fs.add_file(path, "");
+        }
+
+        let conn = info(fs.clone(), "/repo/src/net/http/conn.cc");
+        assert_eq!(conn.root, Path::new("/repo"));
+        assert_eq!(conn.decision.unwrap().reason, DecisionReason::BuildSystem);
+        let package = conn.build_package.unwrap();
+        assert_eq!(package.dir, Path::new("/repo/src/net"));
+        assert_eq!(package.label, "//src/net");
+
+        let gen = info(fs, "/repo/tools/gen.py");
+        assert_eq!(gen.build_package.unwrap().label, "//");
+    }
+
+    #[test]
+    fn test_buck_and_pants_roots() {
+        let mut fs = MemoryFs::new();
+        for path in [
+            "/buck/.buckconfig",
+            "/buck/lib/TARGETS",
+            "/buck/lib/a.py",
+            "/pants/pants.toml",
+            "/pants/docs/readme.md",
+        ] {
+            fs.add_file(path, "");
+        }
+
+        let a = info(fs.clone(), "/buck/lib/a.py");
+        assert_eq!(a.root, Path::new("/buck"));
+        assert_eq!(a.build_package.unwrap().label, "//lib");
+
+        // Files outside every package still get the repository root
+        let readme = info(fs, "/pants/docs/readme.md");
+        assert_eq!(readme.root, Path::new("/pants"));
+        assert!(readme.build_package.is_none());
+    }
+
+    #[test]
+    fn test_other_repositories_fall_back_to_markers() {
+        let mut fs = MemoryFs::new();
+        for path in ["/repo/.git/HEAD", "/repo/Cargo.toml", "/repo/src/main.rs"] {
+            fs.add_file(path, "");
+        }
+
+        let main = info(fs, "/repo/src/main.rs");
+        assert_eq!(main.root, Path::new("/repo"));
+        assert_eq!(main.decision.unwrap().reason, DecisionReason::Innermost);
+        assert!(main.build_package.is_none());
+    }
+
+    #[test]
+    fn test_build_directories_are_packages() {
+        let mut fs = MemoryFs::new();
+        for path in [
+            "/repo/WORKSPACE",
+            "/repo/build/BUILD",
+            "/repo/build/config/gen.py",
+        ] {
+            fs.add_file(path, "");
+        }
+
+        let gen = info(fs, "/repo/build/config/gen.py");
+        assert_eq!(gen.root, Path::new("/repo"));
+        assert_eq!(gen.build_package.unwrap().label, "//build");
+    }
+}
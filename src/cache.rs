@@ -0,0 +1,803 @@
+//! Marker presence and root cache, optionally persisted between runs.
+//!
+//! A persistent cache is one JSON file per marker configuration under
+//! [`default_cache_dir`]. Each entry records the directory's mtime and inode
+//! and the mtimes of the marker files that have content predicates: creating,
+//! deleting or renaming a file changes its directory's mtime, editing a
+//! predicate file changes its own. Entries loaded from disk are only used
+//! while these still match. The root chosen for a directory is cached with
+//! the stamps of every ancestor the decision looked at.
+//!
+//! Like git's index, a directory modified within [`RACY_WINDOW`] of being
+//! read is not persisted: a later change in the same timestamp tick would go
+//! unnoticed. Entries not used for [`EXPIRY_DAYS`] are dropped on save, and a
+//! file keeps at most [`MAX_ENTRIES`] of each kind. The file is only written
+//! when its contents changed.
+
+use crate::{Config, FileSystem, Marker, MarkerRoot, RootDetectionError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Bumped whenever the file format changes; older files are ignored
+const CACHE_VERSION: u32 = 2;
+
+/// Prefix of cache file names, followed by the config fingerprint
+const CACHE_FILE_PREFIX: &str = "markers-";
+
+/// Modifications this recent may share a timestamp with a later one
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Entries unused for this many days are dropped on save
+const EXPIRY_DAYS: u32 = 30;
+
+/// Last-use days are refreshed once they are this old, so that reading a
+/// cache does not rewrite it on every run
+const REFRESH_DAYS: u32 = 7;
+
+/// Most entries of each kind kept in a cache file; the least recently used
+/// go first
+const MAX_ENTRIES: usize = 100_000;
+
+/// Directory holding persistent caches: `$XDG_CACHE_HOME/project-root-detector`,
+/// or `~/.cache/project-root-detector`
+#[must_use]
+pub fn default_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// What a directory looked like when its markers were read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    /// Directory mtime, in nanoseconds since the epoch
+    mtime: u64,
+    /// Directory inode (0 where unavailable)
+    inode: u64,
+    /// Predicate marker files present, with their mtimes
+    files: Vec<(String, u64)>,
+}
+
+impl Stamp {
+    fn of(fs: &dyn FileSystem, dir: &Path, predicates: &PredicateFiles) -> Option<Self> {
+        // Ancestors of a relative path end in the empty path
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let stamp = fs.stamp(dir)?;
+        let mut names = predicates.names.clone();
+        if !predicates.globs.is_empty() {
+            names.extend(
+                fs.read_dir(dir)
+                    .ok()?
+                    .iter()
+                    .filter_map(|entry| entry.file_name()?.to_str())
+                    .filter(|name| {
+                        predicates
+                            .globs
+                            .iter()
+                            .any(|m| m.matches_name(name, predicates.case_insensitive))
+                    })
+                    .map(String::from),
+            );
+            names.sort();
+            names.dedup();
+        }
+
+        Some(Self {
+            mtime: nanos_since_epoch(stamp.modified)?,
+            inode: stamp.inode,
+            files: names
+                .into_iter()
+                .filter_map(|name| {
+                    let stamp = fs.stamp(&dir.join(&name))?;
+                    Some((name, nanos_since_epoch(stamp.modified)?))
+                })
+                .collect(),
+        })
+    }
+
+    /// Whether anything was modified too recently for its mtime to be trusted
+    fn is_racy(&self) -> bool {
+        let threshold = SystemTime::now()
+            .checked_sub(RACY_WINDOW)
+            .and_then(nanos_since_epoch)
+            .unwrap_or(0);
+        self.mtime >= threshold || self.files.iter().any(|(_, mtime)| *mtime >= threshold)
+    }
+}
+
+fn nanos_since_epoch(time: SystemTime) -> Option<u64> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_nanos()).ok()
+}
+
+/// Days since the epoch
+fn today() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| {
+            u32::try_from(since_epoch.as_secs() / 86_400).unwrap_or(u32::MAX)
+        })
+}
+
+/// Marker files whose mtimes are stamped: those with content predicates
+#[derive(Debug, Default)]
+struct PredicateFiles {
+    /// Literal names, sorted
+    names: Vec<String>,
+    /// Name globs, matched against the directory's entries
+    globs: Vec<Marker>,
+    case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    /// Names of the configured markers present, sorted
+    markers: Vec<String>,
+    /// Absent for entries that must not be persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stamp: Option<Stamp>,
+    /// Day the entry was last used
+    #[serde(default)]
+    used: u32,
+    /// Loaded entries are checked against the filesystem on first use
+    #[serde(skip)]
+    verified: bool,
+}
+
+/// Root chosen for the files of a directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RootEntry {
+    /// The directory as it was spelled when looked up; roots are spelled alike
+    dir: PathBuf,
+    root: Option<MarkerRoot>,
+    /// Every ancestor the decision read, with its stamp; absent for entries
+    /// that must not be persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stamps: Option<Vec<(PathBuf, Stamp)>>,
+    /// Day the entry was last used
+    #[serde(default)]
+    used: u32,
+    /// Loaded entries are checked against the filesystem on first use
+    #[serde(skip)]
+    verified: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, Entry>,
+    /// Fingerprint of the strategy and exclusions the roots were chosen with
+    #[serde(default)]
+    roots_config: u64,
+    #[serde(default)]
+    roots: HashMap<PathBuf, RootEntry>,
+}
+
+#[derive(Debug)]
+struct Persistence {
+    path: PathBuf,
+    /// Relative directories are keyed from here, so runs from different
+    /// working directories do not share entries by mistake
+    base: PathBuf,
+    predicate_files: PredicateFiles,
+    roots_config: u64,
+}
+
+/// Thread-safe cache of the markers present in each directory.
+///
+/// Entries record which of [`Config::markers`] a directory contains, so a
+/// cache must only be shared between lookups using the same config.
+/// [`MarkerCache::open`] loads a persistent cache for a config; call
+/// [`MarkerCache::save`] to write back what was learned.
+#[derive(Debug, Default)]
+pub struct MarkerCache {
+    cache: Mutex<HashMap<PathBuf, Entry>>,
+    roots: Mutex<HashMap<PathBuf, RootEntry>>,
+    persistence: Option<Persistence>,
+    dirty: AtomicBool,
+}
+
+impl MarkerCache {
+    /// Create a new empty in-memory marker cache
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the persistent cache for `config` in `cache_dir` (see
+    /// [`default_cache_dir`]). A missing, unreadable or outdated cache file
+    /// starts an empty cache.
+    #[must_use]
+    pub fn open(cache_dir: &Path, config: &Config) -> Self {
+        let path = cache_dir.join(format!(
+            "{CACHE_FILE_PREFIX}{:016x}.json",
+            fingerprint(config)
+        ));
+        let roots_config = roots_fingerprint(config);
+        let (entries, roots) = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| {
+                let roots = if file.roots_config == roots_config {
+                    file.roots
+                } else {
+                    HashMap::new()
+                };
+                (file.entries, roots)
+            })
+            .unwrap_or_default();
+
+        let (globs, literals): (Vec<&Marker>, Vec<&Marker>) = config
+            .markers
+            .iter()
+            .filter(|m| !m.predicates.is_empty())
+            .partition(|m| m.is_glob());
+        let mut names: Vec<String> = literals.iter().map(|m| m.name().to_string()).collect();
+        names.sort();
+
+        Self {
+            cache: Mutex::new(entries),
+            roots: Mutex::new(roots),
+            persistence: Some(Persistence {
+                path,
+                base: std::env::current_dir().unwrap_or_default(),
+                predicate_files: PredicateFiles {
+                    names,
+                    globs: globs.into_iter().cloned().collect(),
+                    case_insensitive: config.case_insensitive,
+                },
+                roots_config,
+            }),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the cache is backed by a file
+    #[must_use]
+    pub const fn is_persistent(&self) -> bool {
+        self.persistence.is_some()
+    }
+
+    /// Write a persistent cache back to disk, if anything changed since it
+    /// was opened. Does nothing for in-memory caches.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RootDetectionError::CacheWrite`] if the cache file cannot be
+    /// written.
+    pub fn save(&self) -> Result<(), RootDetectionError> {
+        let Some(persistence) = &self.persistence else {
+            return Ok(());
+        };
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let oldest = today().saturating_sub(EXPIRY_DAYS);
+        let entries = self
+            .cache
+            .lock()
+            .map(|cache| {
+                most_recent(
+                    cache
+                        .iter()
+                        .filter(|(_, entry)| entry.stamp.is_some() && entry.used >= oldest)
+                        .map(|(dir, entry)| (dir.clone(), entry.clone(), entry.used)),
+                )
+            })
+            .unwrap_or_default();
+        let roots = self
+            .roots
+            .lock()
+            .map(|roots| {
+                most_recent(
+                    roots
+                        .iter()
+                        .filter(|(_, entry)| entry.stamps.is_some() && entry.used >= oldest)
+                        .map(|(dir, entry)| (dir.clone(), entry.clone(), entry.used)),
+                )
+            })
+            .unwrap_or_default();
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries,
+            roots_config: persistence.roots_config,
+            roots,
+        };
+
+        let write_error = |source| RootDetectionError::CacheWrite {
+            path: persistence.path.clone(),
+            source,
+        };
+        let bytes = serde_json::to_vec(&file).map_err(|e| write_error(e.into()))?;
+        if let Some(dir) = persistence.path.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        // Concurrent runs each replace the whole file, never leave it half-written
+        let temp = persistence
+            .path
+            .with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temp, bytes).map_err(write_error)?;
+        fs::rename(&temp, &persistence.path).map_err(write_error)?;
+
+        self.dirty.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Clear the cache (useful when filesystem changes)
+    pub fn clear(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            if !cache.is_empty() {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            cache.clear();
+        }
+        if let Ok(mut roots) = self.roots.lock() {
+            if !roots.is_empty() {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            roots.clear();
+        }
+    }
+
+    /// Number of directories cached
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cache.lock().map_or(0, |cache| cache.len())
+    }
+
+    /// Whether no directory is cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// State of `dir` to record with its markers; taken before reading them,
+    /// so a concurrent change invalidates the entry
+    pub(crate) fn stamp(&self, fs: &dyn FileSystem, dir: &Path) -> Option<Stamp> {
+        let persistence = self.persistence.as_ref()?;
+        Stamp::of(fs, dir, &persistence.predicate_files).filter(|stamp| !stamp.is_racy())
+    }
+
+    /// Whether `stamp` still describes `dir`
+    fn is_current(&self, fs: &dyn FileSystem, dir: &Path, stamp: Option<&Stamp>) -> bool {
+        let default = PredicateFiles::default();
+        let predicate_files = self
+            .persistence
+            .as_ref()
+            .map_or(&default, |p| &p.predicate_files);
+        Stamp::of(fs, dir, predicate_files).as_ref() == stamp
+    }
+
+    /// Last-use day to record for an entry verified today, if it needs updating
+    fn refreshed(&self, used: u32) -> Option<u32> {
+        let today = today();
+        (self.persistence.is_some() && used.saturating_add(REFRESH_DAYS) <= today).then_some(today)
+    }
+
+    /// Key for `dir`: absolute for persistent caches
+    fn key<'p>(&self, dir: &'p Path) -> Cow<'p, Path> {
+        match &self.persistence {
+            Some(persistence) if dir.is_relative() => Cow::Owned(persistence.base.join(dir)),
+            _ => Cow::Borrowed(dir),
+        }
+    }
+
+    pub(crate) fn get(&self, fs: &dyn FileSystem, dir: &Path) -> Option<Vec<String>> {
+        let dir = &*self.key(dir);
+        let entry = self.cache.lock().ok()?.get(dir).cloned()?;
+        if entry.verified {
+            return Some(entry.markers);
+        }
+
+        if self.is_current(fs, dir, entry.stamp.as_ref()) {
+            let refreshed = self.refreshed(entry.used);
+            if let Ok(mut cache) = self.cache.lock() {
+                if let Some(cached) = cache.get_mut(dir) {
+                    cached.verified = true;
+                    if let Some(today) = refreshed {
+                        cached.used = today;
+                        self.dirty.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            Some(entry.markers)
+        } else {
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.remove(dir);
+            }
+            self.dirty.store(true, Ordering::Relaxed);
+            None
+        }
+    }
+
+    pub(crate) fn insert(&self, dir: &Path, markers: Vec<String>, stamp: Option<Stamp>) {
+        let dir = self.key(dir).into_owned();
+        let entry = Entry {
+            markers,
+            stamp,
+            used: today(),
+            verified: true,
+        };
+        if let Ok(mut cache) = self.cache.lock() {
+            if entry.stamp.is_some() && cache.get(&dir) != Some(&entry) {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            cache.insert(dir, entry);
+        }
+    }
+
+    /// The root cached for files in `dir`: `Some(None)` if they have none.
+    /// Loaded entries are only used while every ancestor they read is unchanged.
+    // Files with no root are cached too, as `Some(None)`
+    #[allow(clippy::option_option)]
+    pub(crate) fn root(&self, fs: &dyn FileSystem, dir: &Path) -> Option<Option<MarkerRoot>> {
+        let key = &*self.key(dir);
+        let entry = self.roots.lock().ok()?.get(key).cloned()?;
+        if entry.dir != dir {
+            return None;
+        }
+        if entry.verified {
+            return Some(entry.root);
+        }
+
+        let current = entry.stamps.as_ref().is_some_and(|stamps| {
+            stamps
+                .iter()
+                .all(|(ancestor, stamp)| self.is_current(fs, ancestor, Some(stamp)))
+        });
+        if current {
+            let refreshed = self.refreshed(entry.used);
+            if let Ok(mut roots) = self.roots.lock() {
+                if let Some(cached) = roots.get_mut(key) {
+                    cached.verified = true;
+                    if let Some(today) = refreshed {
+                        cached.used = today;
+                        self.dirty.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            Some(entry.root)
+        } else {
+            if let Ok(mut roots) = self.roots.lock() {
+                roots.remove(key);
+            }
+            self.dirty.store(true, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Stamps of the ancestors a root decision reads, if they can all be persisted
+    pub(crate) fn stamps<'a>(
+        &self,
+        fs: &dyn FileSystem,
+        ancestors: impl IntoIterator<Item = &'a Path>,
+    ) -> Option<Vec<(PathBuf, Stamp)>> {
+        self.persistence.as_ref()?;
+        ancestors
+            .into_iter()
+            .map(|ancestor| Some((ancestor.to_path_buf(), self.stamp(fs, ancestor)?)))
+            .collect()
+    }
+
+    /// Cache the root chosen for files in `dir`, persisted if `stamps` are given
+    pub(crate) fn insert_root(
+        &self,
+        dir: &Path,
+        root: Option<&MarkerRoot>,
+        stamps: Option<Vec<(PathBuf, Stamp)>>,
+    ) {
+        let persisted = stamps.is_some();
+        let entry = RootEntry {
+            dir: dir.to_path_buf(),
+            root: root.cloned(),
+            stamps,
+            used: today(),
+            verified: true,
+        };
+        if let Ok(mut roots) = self.roots.lock() {
+            if persisted {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            roots.insert(self.key(dir).into_owned(), entry);
+        }
+    }
+}
+
+/// The `MAX_ENTRIES` most recently used of `entries`
+fn most_recent<T>(entries: impl Iterator<Item = (PathBuf, T, u32)>) -> HashMap<PathBuf, T> {
+    let mut entries: Vec<(PathBuf, T, u32)> = entries.collect();
+    if entries.len() > MAX_ENTRIES {
+        entries.sort_by(|a, b| b.2.cmp(&a.2));
+        entries.truncate(MAX_ENTRIES);
+    }
+    entries
+        .into_iter()
+        .map(|(dir, entry, _)| (dir, entry))
+        .collect()
+}
+
+/// Stable hash of everything that decides which markers a directory has
+fn fingerprint(config: &Config) -> u64 {
+    let mut markers: Vec<String> = config
+        .markers
+        .iter()
+        .map(|m| format!("{}\0{}\0{:?}", m.name(), m.weight, m.predicates))
+        .collect();
+    markers.sort();
+
+    let bytes = CACHE_VERSION
+        .to_le_bytes()
+        .into_iter()
+        .chain([u8::from(config.case_insensitive)])
+        .chain(
+            markers
+                .into_iter()
+                .flat_map(|marker| marker.into_bytes().into_iter().chain([0xff])),
+        );
+    fnv1a(bytes)
+}
+
+/// FNV-1a: unlike `DefaultHasher`, stable across builds
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Stable hash of the rest of the config that decides roots
+fn roots_fingerprint(config: &Config) -> u64 {
+    fnv1a(
+        std::iter::once(format!("{:?}", config.strategy))
+            .chain(config.exclusions.fingerprint())
+            .flat_map(|part| part.into_bytes().into_iter().chain([0xff])),
+    )
+}
+
+/// Summary of the persistent caches in a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Cache files (one per marker configuration)
+    pub files: usize,
+    /// Directories recorded across all files
+    pub entries: usize,
+    /// Directories with a recorded root across all files
+    pub roots: usize,
+    /// Total size of the cache files
+    pub bytes: u64,
+}
+
+fn cache_files(cache_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(cache_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(CACHE_FILE_PREFIX))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Count the persistent caches in `cache_dir`
+#[must_use]
+pub fn cache_stats(cache_dir: &Path) -> CacheStats {
+    cache_files(cache_dir)
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .fold(CacheStats::default(), |mut stats, bytes| {
+            stats.files += 1;
+            stats.bytes += bytes.len() as u64;
+            if let Ok(file) = serde_json::from_slice::<CacheFile>(&bytes) {
+                stats.entries += file.entries.len();
+                stats.roots += file.roots.len();
+            }
+            stats
+        })
+}
+
+/// Delete the persistent caches in `cache_dir`, returning how many were removed
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::CacheWrite`] if a cache file cannot be removed.
+pub fn clear_cache(cache_dir: &Path) -> Result<usize, RootDetectionError> {
+    let files = cache_files(cache_dir);
+    for path in &files {
+        fs::remove_file(path).map_err(|source| RootDetectionError::CacheWrite {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_marker_root, MarkerPredicate, StdFs};
+    use tempfile::TempDir;
+
+    /// Backdate a path out of the racy window
+    fn age(path: &Path) {
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::open(path).unwrap().set_modified(old).unwrap();
+    }
+
+    fn lookup(cache: &MarkerCache, dir: &Path) -> Option<Vec<String>> {
+        cache.get(&StdFs, dir)
+    }
+
+    fn fill(cache: &MarkerCache, dir: &Path, markers: &[&str]) {
+        let stamp = cache.stamp(&StdFs, dir);
+        let markers = markers.iter().map(|m| (*m).to_string()).collect();
+        cache.insert(dir, markers, stamp);
+    }
+
+    #[test]
+    fn test_persisted_entries_survive_until_changed() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        let project = temp.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        age(&project.join("package.json"));
+        age(&project);
+
+        let config = Config::default().with_builtin_predicates();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        fill(&cache, &project, &[]);
+        cache.save().unwrap();
+
+        // A new run sees the entry
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert_eq!(lookup(&cache, &project), Some(vec![]));
+
+        // Editing a predicate file invalidates it
+        fs::write(project.join("package.json"), r#"{"name": "x"}"#).unwrap();
+        age(&project.join("package.json"));
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert_eq!(lookup(&cache, &project), None);
+
+        // So does adding a file
+        fill(&cache, &project, &["package.json"]);
+        cache.save().unwrap();
+        fs::write(project.join("Cargo.toml"), "").unwrap();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert_eq!(lookup(&cache, &project), None);
+    }
+
+    #[test]
+    fn test_racy_and_foreign_entries_are_not_reused() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        let project = temp.path().join("project");
+        fs::create_dir(&project).unwrap();
+
+        // Just modified: kept in memory, not persisted
+        let config = Config::default();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        fill(&cache, &project, &[]);
+        assert_eq!(lookup(&cache, &project), Some(vec![]));
+        cache.save().unwrap();
+        assert_eq!(cache_stats(&cache_dir).entries, 0);
+
+        // Another marker configuration gets its own file
+        age(&project);
+        fill(&cache, &project, &[]);
+        cache.save().unwrap();
+        let other = config.with_marker(Marker::new("BUILD"));
+        assert!(MarkerCache::open(&cache_dir, &other).is_empty());
+
+        let stats = cache_stats(&cache_dir);
+        assert_eq!((stats.files, stats.entries), (1, 1));
+        assert_eq!(clear_cache(&cache_dir).unwrap(), 1);
+        assert_eq!(cache_stats(&cache_dir), CacheStats::default());
+    }
+
+    #[test]
+    fn test_roots_survive_until_an_ancestor_changes() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        // The exclusion boundary keeps the walk out of the shared temp dir
+        let project = temp.path().join("node_modules").join("project");
+        let src = project.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(project.join("Cargo.toml"), "").unwrap();
+        for path in [project.join("Cargo.toml"), src.clone(), project.clone()] {
+            age(&path);
+        }
+        let file = src.join("main.rs");
+
+        let config = Config::default();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        let root = find_marker_root(&file, &config, Some(&cache)).unwrap();
+        assert_eq!(root.root, project);
+        cache.save().unwrap();
+        assert_eq!(cache_stats(&cache_dir).roots, 1);
+
+        // A new run reuses the root, and has nothing to write back
+        let cache = MarkerCache::open(&cache_dir, &config);
+        let cached = cache.root(&StdFs, &src).flatten().map(|root| root.root);
+        assert_eq!(cached, Some(project));
+        assert!(!cache.dirty.load(Ordering::Relaxed));
+
+        // A marker appearing below the root invalidates it
+        fs::write(src.join("Cargo.toml"), "").unwrap();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert!(cache.root(&StdFs, &src).is_none());
+        let root = find_marker_root(&file, &config, Some(&cache)).unwrap();
+        assert_eq!(root.root, src);
+
+        // Another strategy does not reuse the roots
+        let config = config.with_strategy(crate::RootStrategy::Outermost);
+        assert!(MarkerCache::open(&cache_dir, &config)
+            .root(&StdFs, &src)
+            .is_none());
+    }
+
+    #[test]
+    fn test_unused_entries_expire() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        let (old, recent) = (temp.path().join("old"), temp.path().join("recent"));
+        for dir in [&old, &recent] {
+            fs::create_dir(dir).unwrap();
+            age(dir);
+        }
+
+        let config = Config::default();
+        let cache = MarkerCache::open(&cache_dir, &config);
+        fill(&cache, &old, &[]);
+        fill(&cache, &recent, &[]);
+        cache.cache.lock().unwrap().get_mut(&old).unwrap().used = today() - EXPIRY_DAYS - 1;
+        cache.save().unwrap();
+
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert_eq!(lookup(&cache, &old), None);
+        assert_eq!(lookup(&cache, &recent), Some(vec![]));
+    }
+
+    #[test]
+    fn test_glob_predicate_files_are_stamped() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        let project = temp.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("app.csproj"), "<Project>").unwrap();
+        age(&project.join("app.csproj"));
+        age(&project);
+
+        let sdk = MarkerPredicate::Contains {
+            any: vec!["Sdk=".to_string()],
+            ignore_case: false,
+        };
+        let config = Config::default().with_marker(Marker::new("*.csproj").with_predicate(sdk));
+        let cache = MarkerCache::open(&cache_dir, &config);
+        fill(&cache, &project, &[]);
+        cache.save().unwrap();
+
+        // Editing the project file does not touch the directory's mtime
+        fs::write(project.join("app.csproj"), r#"<Project Sdk="x">"#).unwrap();
+        age(&project.join("app.csproj"));
+        let cache = MarkerCache::open(&cache_dir, &config);
+        assert_eq!(lookup(&cache, &project), None);
+    }
+}
@@ -0,0 +1,467 @@
+//! Loading [`Config`] from `.rootdetect.toml` / `.rootdetect.json` files.
+//!
+//! A config file only lists what differs from the defaults:
+//!
+//! ```toml
+//! # Extra markers and exclusions are merged over the defaults. A table sets
+//! # the marker's weight and content predicates; naming a default marker
+//! # overrides only the given settings
+//! markers = [
+//!     "WORKSPACE",
+//!     { name = "CMakeLists.txt", weight = 20 },
+//!     { name = "Makefile", contains = ["all:"] },
+//!     { name = "pyproject.toml", toml_keys = ["project", "tool.hatch"] },
+//!     { name = "package.json", check_contents = false },
+//! ]
+//! exclusions = ["third_party"]
+//!
+//! # Drop selected defaults
+//! remove_markers = ["CMakeLists.txt"]
+//! remove_exclusions = ["vendor"]
+//!
+//! # Set to false to start from empty lists instead of the defaults
+//! extend_defaults = true
+//!
+//! case_insensitive = false
+//!
+//! # Only count default markers whose contents look like a project (a
+//! # package.json with a name, a pyproject.toml with [project], ...)
+//! builtin_predicates = true
+//!
+//! # Let CMakeLists.txt defer to a heavier marker (.git, Cargo.toml, ...) in
+//! # an enclosing directory
+//! weak_defaults = true
+//!
+//! # Add the .NET solution and project globs (*.sln, *.csproj, *.fsproj) and
+//! # the weak Directory.Build.props and global.json markers
+//! dotnet = true
+//!
+//! # Report enclosing workspaces (Cargo, ...)
+//! resolve_workspaces = true
+//!
+//! # "innermost" (default), "outermost", "vcs", or a priority list
+//! strategy = { priority = ["Cargo.toml", ".git"] }
+//! ```
+
+use crate::{Config, Marker, MarkerPredicate, RootDetectionError, RootStrategy};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// File names searched by [`Config::discover`], in order of preference
+pub const CONFIG_FILE_NAMES: &[&str] = &[".rootdetect.toml", ".rootdetect.json"];
+
+/// On-disk representation of a configuration file
+// Keys mirror independent `Config` switches
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    extend_defaults: bool,
+    markers: Vec<MarkerEntry>,
+    exclusions: Vec<String>,
+    remove_markers: Vec<String>,
+    remove_exclusions: Vec<String>,
+    case_insensitive: Option<bool>,
+    builtin_predicates: bool,
+    weak_defaults: bool,
+    dotnet: bool,
+    resolve_workspaces: Option<bool>,
+    strategy: Option<RootStrategy>,
+}
+
+/// A marker given by name, or as a table of settings
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MarkerEntry {
+    Name(String),
+    Table(MarkerTable),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkerTable {
+    name: String,
+    weight: Option<u32>,
+    contains: Option<Vec<String>>,
+    #[serde(default)]
+    ignore_case: bool,
+    toml_keys: Option<Vec<String>>,
+    json_keys: Option<Vec<String>>,
+    /// `false` drops all content predicates, including built-in ones
+    check_contents: Option<bool>,
+}
+
+impl MarkerEntry {
+    /// Apply the entry over the marker of the same name, if already configured
+    fn merge(self, existing: Option<&Marker>) -> Marker {
+        let table = match self {
+            Self::Name(name) => {
+                return existing.cloned().unwrap_or_else(|| Marker::new(name));
+            }
+            Self::Table(table) => table,
+        };
+
+        let mut marker = existing.cloned().unwrap_or_else(|| Marker::new(table.name));
+        if let Some(weight) = table.weight {
+            marker.weight = weight;
+        }
+
+        let predicates: Vec<MarkerPredicate> = [
+            table.contains.map(|any| MarkerPredicate::Contains {
+                any,
+                ignore_case: table.ignore_case,
+            }),
+            table.toml_keys.map(MarkerPredicate::TomlKeys),
+            table.json_keys.map(MarkerPredicate::JsonKeys),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !predicates.is_empty() || table.check_contents == Some(false) {
+            marker.predicates = predicates;
+        }
+
+        marker
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Table(MarkerTable { name, .. }) => name,
+        }
+    }
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            extend_defaults: true,
+            markers: Vec::new(),
+            exclusions: Vec::new(),
+            remove_markers: Vec::new(),
+            remove_exclusions: Vec::new(),
+            case_insensitive: None,
+            builtin_predicates: false,
+            weak_defaults: false,
+            dotnet: false,
+            resolve_workspaces: None,
+            strategy: None,
+        }
+    }
+}
+
+impl ConfigFile {
+    fn parse(path: &Path, contents: &str) -> Result<Self, RootDetectionError> {
+        let is_json = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+
+        let parsed = if is_json {
+            serde_json::from_str(contents).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(contents).map_err(|e| e.to_string())
+        };
+
+        parsed.map_err(|message| RootDetectionError::ConfigParse {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    /// Settings over the defaults, with path globs relative to `dir`
+    fn into_config(self, dir: &Path) -> Config {
+        let mut config = if self.extend_defaults {
+            Config::default()
+        } else {
+            Config::new(&[], &[])
+        };
+        if self.builtin_predicates {
+            config = config.with_builtin_predicates();
+        }
+        if self.weak_defaults {
+            config = config.with_weak_defaults();
+        }
+        if self.dotnet {
+            config = config.with_dotnet_markers();
+        }
+
+        for entry in self.markers {
+            let existing = config.markers.get(entry.name());
+            let marker = entry.merge(existing);
+            config.markers.replace(marker);
+        }
+        config.exclusions.extend(self.exclusions);
+        config.exclusions.anchor_at(dir);
+        for marker in &self.remove_markers {
+            config.markers.remove(marker.as_str());
+        }
+        for exclusion in &self.remove_exclusions {
+            config.exclusions.remove(exclusion);
+        }
+        if let Some(case_insensitive) = self.case_insensitive {
+            config.case_insensitive = case_insensitive;
+        }
+        if let Some(resolve_workspaces) = self.resolve_workspaces {
+            config.resolve_workspaces = resolve_workspaces;
+        }
+        if let Some(strategy) = self.strategy {
+            config.strategy = strategy;
+        }
+
+        config
+    }
+}
+
+/// Find the nearest configuration file in `start_dir` or any of its ancestors
+#[must_use]
+pub fn find_config_file(start_dir: &Path) -> Option<PathBuf> {
+    let start = start_dir
+        .canonicalize()
+        .unwrap_or_else(|_| start_dir.to_path_buf());
+
+    start.ancestors().find_map(|dir| {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+impl Config {
+    /// Load a configuration file, merging its settings over the defaults.
+    ///
+    /// Files ending in `.json` are parsed as JSON, everything else as TOML.
+    /// Path-glob exclusions are relative to the file's directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid config file.
+    pub fn from_file(path: &Path) -> Result<Self, RootDetectionError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|source| RootDetectionError::ConfigRead {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        Ok(ConfigFile::parse(path, &contents)?.into_config(&dir))
+    }
+
+    /// Load the nearest `.rootdetect.toml` / `.rootdetect.json` found in `start_dir`
+    /// or its ancestors, falling back to [`Config::default`] when there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a config file is found but cannot be read or parsed.
+    pub fn discover(start_dir: &Path) -> Result<Self, RootDetectionError> {
+        find_config_file(start_dir)
+            .map_or_else(|| Ok(Self::default()), |path| Self::from_file(&path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_toml_config_merges_over_defaults() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.toml");
+        fs::write(
+            &path,
+            "markers = [\"WORKSPACE\"]\n\
+             exclusions = [\"third_party\"]\n\
+             remove_markers = [\"CMakeLists.txt\"]\n\
+             case_insensitive = true\n\
+             resolve_workspaces = true\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert!(config.markers.contains("WORKSPACE"));
+        assert!(config.markers.contains(".git"));
+        assert!(!config.markers.contains("CMakeLists.txt"));
+        assert!(config.exclusions.contains("third_party"));
+        assert!(config.exclusions.contains("node_modules"));
+        assert!(config.case_insensitive);
+        assert!(config.resolve_workspaces);
+    }
+
+    #[test]
+    fn test_weighted_markers() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.toml");
+        fs::write(
+            &path,
+            "weak_defaults = true\n\
+             dotnet = true\n\
+             markers = [\"WORKSPACE\", { name = \"CMakeLists.txt\", weight = 20 }]\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(
+            config.markers.get("global.json").unwrap().weight,
+            Marker::WEAK_WEIGHT
+        );
+        assert_eq!(
+            config.markers.get("WORKSPACE").unwrap().weight,
+            Marker::DEFAULT_WEIGHT
+        );
+        assert_eq!(config.markers.get("CMakeLists.txt").unwrap().weight, 20);
+        assert_eq!(
+            config.markers.len(),
+            crate::DEFAULT_MARKERS.len()
+                + crate::DOTNET_MARKERS.len()
+                + crate::DOTNET_SETTINGS_MARKERS.len()
+                + 1,
+            "re-weighting a default replaces it"
+        );
+    }
+
+    #[test]
+    fn test_marker_predicates() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.toml");
+        fs::write(
+            &path,
+            "builtin_predicates = true\n\
+             markers = [\n\
+             \"pyproject.toml\",\n\
+             { name = \"Makefile\", contains = [\"ALL:\"], ignore_case = true },\n\
+             { name = \"package.json\", check_contents = false },\n\
+             ]\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        // Naming a default keeps its built-in predicate
+        assert_eq!(
+            config
+                .markers
+                .get("pyproject.toml")
+                .unwrap()
+                .predicates
+                .len(),
+            1
+        );
+        assert!(config
+            .markers
+            .get("package.json")
+            .unwrap()
+            .predicates
+            .is_empty());
+        assert_eq!(
+            config.markers.get("Makefile").unwrap().predicates,
+            vec![MarkerPredicate::Contains {
+                any: vec!["ALL:".to_string()],
+                ignore_case: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_path_globs_are_relative_to_config_file() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for dir in ["repo/docs/generated", "elsewhere/docs/generated"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("ref.py"), "").unwrap();
+        }
+        let path = root.join("repo/.rootdetect.toml");
+        fs::write(&path, "exclusions = [\"docs/generated\"]\n").unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        let excluded =
+            |dir: &str| crate::is_excluded(&root.join(dir).join("ref.py"), &config, None);
+        assert!(excluded("repo/docs/generated"));
+        assert!(!excluded("elsewhere/docs/generated"));
+    }
+
+    #[test]
+    fn test_json_config_without_defaults() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.json");
+        fs::write(
+            &path,
+            r#"{"extend_defaults": false, "markers": ["BUILD"], "exclusions": ["out"]}"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.markers.len(), 1);
+        assert!(config.markers.contains("BUILD"));
+        assert_eq!(config.exclusions.len(), 1);
+        assert!(config.exclusions.contains("out"));
+    }
+
+    #[test]
+    fn test_strategy_setting() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.toml");
+
+        fs::write(&path, "strategy = \"vcs\"\n").unwrap();
+        assert_eq!(
+            Config::from_file(&path).unwrap().strategy,
+            RootStrategy::Vcs
+        );
+
+        fs::write(&path, "strategy = { priority = [\"go.mod\", \".git\"] }\n").unwrap();
+        assert_eq!(
+            Config::from_file(&path).unwrap().strategy,
+            RootStrategy::Priority(vec!["go.mod".to_string(), ".git".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_is_error() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".rootdetect.toml");
+        fs::write(&path, "unknown_key = 1\n").unwrap();
+
+        assert!(matches!(
+            Config::from_file(&path),
+            Err(RootDetectionError::ConfigParse { .. })
+        ));
+        assert!(matches!(
+            Config::from_file(&temp.path().join("missing.toml")),
+            Err(RootDetectionError::ConfigRead { .. })
+        ));
+    }
+
+    #[test]
+    fn test_discover_nearest_config() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("team/service/src")).unwrap();
+        fs::write(
+            temp.path().join(".rootdetect.toml"),
+            "markers = [\"OUTER\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("team/.rootdetect.toml"),
+            "markers = [\"INNER\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::discover(&temp.path().join("team/service/src")).unwrap();
+        assert!(config.markers.contains("INNER"));
+        assert!(!config.markers.contains("OUTER"));
+
+        let config = Config::discover(temp.path()).unwrap();
+        assert!(config.markers.contains("OUTER"));
+    }
+
+    #[test]
+    fn test_discover_without_config_uses_defaults() {
+        let temp = TempDir::new().unwrap();
+        let config = Config::discover(temp.path()).unwrap();
+        assert!(config.markers.contains(".git"));
+    }
+}
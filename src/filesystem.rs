@@ -0,0 +1,341 @@
+//! Filesystem access used by detection.
+//!
+//! Every lookup made while detecting roots goes through [`Config::filesystem`],
+//! so detection can run over the real disk ([`StdFs`], the default), an
+//! in-memory tree ([`MemoryFs`]) or anything else that can list directories
+//! and read files.
+//!
+//! [`Config::filesystem`]: crate::Config::filesystem
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Modification time and identity of a path, used to validate cached lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    /// Last modification time
+    pub modified: SystemTime,
+    /// Inode number, or 0 where the platform has none
+    pub inode: u64,
+}
+
+/// Read-only view of a filesystem
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    /// Paths of the entries in `dir`, in no particular order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` does not exist or is not a readable directory.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Whether `path` is a directory, following symlinks
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Whether `path` is a regular file, following symlinks
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Whether anything exists at `path`
+    fn exists(&self, path: &Path) -> bool {
+        self.is_dir(path) || self.is_file(path)
+    }
+
+    /// Absolute path with symlinks and `.` / `..` resolved
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` does not exist.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Contents of the file at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` is not a readable UTF-8 file.
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Modification time and identity of `path`, if the filesystem tracks
+    /// them. Paths without a stamp are never persisted in a cache.
+    fn stamp(&self, _path: &Path) -> Option<FileStamp> {
+        None
+    }
+}
+
+/// The real filesystem, through `std::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFs;
+
+impl FileSystem for StdFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        // The empty path that relative paths end in is the current directory
+        if dir.as_os_str().is_empty() {
+            return Ok(std::fs::read_dir(".")?
+                .flatten()
+                .map(|entry| PathBuf::from(entry.file_name()))
+                .collect());
+        }
+        Ok(std::fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect())
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn stamp(&self, path: &Path) -> Option<FileStamp> {
+        let meta = std::fs::metadata(path).ok()?;
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&meta);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Some(FileStamp {
+            modified: meta.modified().ok()?,
+            inode,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(String),
+    /// Full paths of the entries
+    Dir(BTreeSet<PathBuf>),
+}
+
+/// In-memory tree of directories and files.
+///
+/// Paths are normalized lexically (`.` and `..` resolved) and there are no
+/// symlinks, so a path's canonical form is itself. Adding a file or directory
+/// creates its missing parents.
+///
+/// ```
+/// use project_root_detector::{find_roots_batch, Config, MemoryFs};
+/// use std::path::{Path, PathBuf};
+///
+/// let fs = MemoryFs::new()
+///     .with_file("/repo/Cargo.toml", "[package]\nname = \"app\"\n")
+///     .with_file("/repo/src/main.rs", "fn main() {}\n");
+/// let config = Config::default().with_filesystem(fs);
+///
+/// let results = find_roots_batch([Path::new("/repo/src/main.rs")], &config);
+/// assert_eq!(results[0].1, Some(PathBuf::from("/repo")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    nodes: BTreeMap<PathBuf, Node>,
+}
+
+impl MemoryFs {
+    /// Create an empty tree
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, replacing any existing file at `path`
+    pub fn add_file(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        let path = normalize_lexically(path.as_ref());
+        self.add_parents(&path);
+        self.nodes.insert(path, Node::File(contents.into()));
+    }
+
+    /// Add an empty directory (no-op if it already exists)
+    pub fn add_dir(&mut self, path: impl AsRef<Path>) {
+        let path = normalize_lexically(path.as_ref());
+        self.add_parents(&path);
+        if !matches!(self.nodes.get(&path), Some(Node::Dir(_))) {
+            self.nodes.insert(path, Node::Dir(BTreeSet::new()));
+        }
+    }
+
+    /// Add a file, see [`MemoryFs::add_file`]
+    #[must_use]
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.add_file(path, contents);
+        self
+    }
+
+    /// Add a directory, see [`MemoryFs::add_dir`]
+    #[must_use]
+    pub fn with_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.add_dir(path);
+        self
+    }
+
+    /// Register `path` in each of its ancestors, creating them as needed.
+    /// Relative paths hang off the empty path, which acts as their root.
+    fn add_parents(&mut self, path: &Path) {
+        let mut child = path;
+        while let Some(parent) = child.parent() {
+            let node = self
+                .nodes
+                .entry(parent.to_path_buf())
+                .or_insert_with(|| Node::Dir(BTreeSet::new()));
+            match node {
+                Node::Dir(entries) => {
+                    if !entries.insert(child.to_path_buf()) {
+                        return; // Ancestors already know about `parent`
+                    }
+                }
+                Node::File(_) => *node = Node::Dir(BTreeSet::from([child.to_path_buf()])),
+            }
+            child = parent;
+        }
+    }
+
+    fn node(&self, path: &Path) -> Option<&Node> {
+        self.nodes.get(&normalize_lexically(path))
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        match self.node(dir) {
+            Some(Node::Dir(entries)) => Ok(entries.iter().cloned().collect()),
+            Some(Node::File(_)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a directory",
+            )),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.node(path), Some(Node::Dir(_)))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        matches!(self.node(path), Some(Node::File(_)))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let normalized = normalize_lexically(path);
+        if self.nodes.contains_key(&normalized) {
+            Ok(normalized)
+        } else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.node(path) {
+            Some(Node::File(contents)) => Ok(contents.clone()),
+            Some(Node::Dir(_)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "is a directory",
+            )),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{discover_roots, traverse_and_detect, Config, TraversalOptions};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_memory_fs_tree() {
+        let mut fs = MemoryFs::new().with_file("/a/b/c.txt", "hello");
+        fs.add_dir("/a/empty");
+
+        assert!(fs.is_dir(Path::new("/a")));
+        assert!(fs.is_dir(Path::new("/a/b")));
+        assert!(fs.is_file(Path::new("/a/b/c.txt")));
+        assert!(fs.exists(Path::new("/a/./b/../b/c.txt")));
+        assert!(!fs.exists(Path::new("/a/missing")));
+        assert_eq!(fs.read_to_string(Path::new("/a/b/c.txt")).unwrap(), "hello");
+        assert!(fs.read_to_string(Path::new("/a/b")).is_err());
+        assert_eq!(
+            fs.read_dir(Path::new("/a")).unwrap(),
+            vec![PathBuf::from("/a/b"), PathBuf::from("/a/empty")]
+        );
+        assert_eq!(
+            fs.canonicalize(Path::new("/a/b/../empty")).unwrap(),
+            PathBuf::from("/a/empty")
+        );
+        assert!(fs.canonicalize(Path::new("/nope")).is_err());
+        assert_eq!(fs.stamp(Path::new("/a")), None);
+    }
+
+    #[test]
+    fn test_std_fs_empty_path_is_current_dir() {
+        // Tests run from the crate root
+        let entries = StdFs.read_dir(Path::new("")).unwrap();
+        assert!(entries.contains(&PathBuf::from("Cargo.toml")));
+        assert!(entries.iter().all(|entry| entry.components().count() == 1));
+        assert_eq!(entries.len(), StdFs.read_dir(Path::new(".")).unwrap().len());
+    }
+
+    #[test]
+    fn test_detection_over_memory_fs() {
+        let fs = MemoryFs::new()
+            .with_file("/code/app/Cargo.toml", "[package]\nname = \"app\"\n")
+            .with_file("/code/app/src/main.rs", "")
+            .with_file("/code/app/node_modules/dep/index.js", "")
+            .with_file("/code/tool/package.json", "{}")
+            .with_file("/code/tool/index.js", "")
+            .with_file("/code/scripts/a/x.py", "")
+            .with_file("/code/scripts/b/y.py", "");
+        let config = Config::default().with_filesystem(fs);
+        let options = TraversalOptions::default().with_extensions(&["rs", "js", "py"]);
+
+        let roots: HashSet<(String, Option<String>)> =
+            traverse_and_detect(Path::new("/code"), &config, &options)
+                .into_iter()
+                .map(|r| {
+                    let display = |p: &Path| p.display().to_string();
+                    (display(&r.file), r.root.as_deref().map(display))
+                })
+                .collect();
+
+        let expected = |file: &str, root: &str| (file.to_string(), Some(root.to_string()));
+        assert!(roots.contains(&expected("/code/app/src/main.rs", "/code/app")));
+        assert!(roots.contains(&expected("/code/tool/index.js", "/code/tool")));
+        // Orphans are grouped with their sibling source directories
+        assert!(roots.contains(&expected("/code/scripts/a/x.py", "/code")));
+        // node_modules is never walked
+        assert_eq!(roots.len(), 4);
+
+        let roots = discover_roots(Path::new("/code"), &config, &options);
+        assert!(roots.contains(Path::new("/code/app")));
+    }
+}
@@ -0,0 +1,428 @@
+//! Tracked files read straight from a repository's `.git/index`.
+//!
+//! This gives the same set of files as `git ls-files` without running git:
+//!
+//! - index versions 2, 3 and 4, with SHA-1 or SHA-256 object names;
+//! - entries marked skip-worktree by a sparse checkout, and the directory
+//!   entries of a sparse index, are left out since they are not on disk;
+//! - submodule gitlinks are replaced by the files of the submodule's own
+//!   index when it is checked out, and skipped otherwise;
+//! - conflicted paths are listed once.
+//!
+//! Split indexes (`core.splitIndex`) are not supported.
+
+use crate::RootDetectionError;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extended flag set on entries outside a sparse checkout
+const SKIP_WORKTREE: u16 = 0x4000;
+
+/// Files tracked by git under `dir`, which may be anywhere inside a worktree.
+///
+/// Paths are `dir` joined with each file's path relative to `dir`, in index
+/// order (sorted, with submodule files in place of their gitlink).
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::GitIndexRead`] if `dir` is not inside a git
+/// worktree or an index cannot be read or parsed.
+pub fn git_tracked_files(dir: &Path) -> Result<Vec<PathBuf>, RootDetectionError> {
+    let (worktree, prefix) = locate_worktree(dir)?;
+    let mut files = Vec::new();
+    collect_worktree(&worktree, Path::new(""), &prefix, &mut files)?;
+    Ok(files
+        .iter()
+        .filter_map(|file| file.strip_prefix(&prefix).ok())
+        .map(|relative| dir.join(relative))
+        .collect())
+}
+
+/// Top of the worktree enclosing `dir`, and `dir` relative to it
+pub fn locate_worktree(dir: &Path) -> Result<(PathBuf, PathBuf), RootDetectionError> {
+    let canonical = dir
+        .canonicalize()
+        .map_err(|source| read_error(dir, source))?;
+    canonical
+        .ancestors()
+        .filter(|ancestor| ancestor.join(".git").exists())
+        .find_map(|ancestor| {
+            let prefix = canonical.strip_prefix(ancestor).ok()?;
+            Some((ancestor.to_path_buf(), prefix.to_path_buf()))
+        })
+        .ok_or_else(|| {
+            read_error(
+                dir,
+                io::Error::new(io::ErrorKind::NotFound, "not inside a git worktree"),
+            )
+        })
+}
+
+pub fn read_error(path: &Path, source: io::Error) -> RootDetectionError {
+    RootDetectionError::GitIndexRead {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Append the files of the worktree at `worktree` (shown as `base`) that lie
+/// under or above `prefix`
+fn collect_worktree(
+    worktree: &Path,
+    base: &Path,
+    prefix: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), RootDetectionError> {
+    let dot_git = worktree.join(".git");
+    let git_dir = git_dir(&dot_git).map_err(|source| read_error(&dot_git, source))?;
+    let index_path = git_dir.join("index");
+    let bytes = match fs::read(&index_path) {
+        Ok(bytes) => bytes,
+        // Nothing was ever staged
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(source) => return Err(read_error(&index_path, source)),
+    };
+    let entries = parse_index(&bytes, hash_len(&git_dir))
+        .map_err(|source| read_error(&index_path, source))?;
+
+    for entry in entries {
+        let path = base.join(&entry.path);
+        match entry.kind {
+            EntryKind::File if path.starts_with(prefix) => files.push(path),
+            EntryKind::Gitlink if path.starts_with(prefix) || prefix.starts_with(&path) => {
+                let submodule = worktree.join(&entry.path);
+                if submodule.join(".git").exists() {
+                    collect_worktree(&submodule, &path, prefix, files)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The git directory behind a `.git` directory or `gitdir:` file
+pub fn git_dir(dot_git: &Path) -> io::Result<PathBuf> {
+    if dot_git.is_dir() {
+        return Ok(dot_git.to_path_buf());
+    }
+    let contents = fs::read_to_string(dot_git)?;
+    let target = contents
+        .strip_prefix("gitdir:")
+        .map(str::trim)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid .git file"))?;
+    Ok(dot_git
+        .parent()
+        .map_or_else(|| PathBuf::from(target), |parent| parent.join(target)))
+}
+
+/// Length of object names: 32 bytes with `extensions.objectFormat = sha256`
+pub fn hash_len(git_dir: &Path) -> usize {
+    let config = fs::read_to_string(common_dir(git_dir).join("config")).unwrap_or_default();
+    let sha256 = config.lines().any(|line| {
+        let line: String = line.split_whitespace().collect();
+        line.eq_ignore_ascii_case("objectformat=sha256")
+    });
+    if sha256 {
+        32
+    } else {
+        20
+    }
+}
+
+/// Where objects, refs and config live: linked worktrees share the main
+/// repository's
+pub fn common_dir(git_dir: &Path) -> PathBuf {
+    fs::read_to_string(git_dir.join("commondir"))
+        .map_or_else(|_| git_dir.to_path_buf(), |dir| git_dir.join(dir.trim()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    /// Regular file or symlink
+    File,
+    /// Submodule commit
+    Gitlink,
+    /// Directory entry of a sparse index
+    SparseDir,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexEntry {
+    path: PathBuf,
+    kind: EntryKind,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("truncated index"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// NUL-terminated name, without the NUL
+    fn name(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.bytes[self.pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid("unterminated path"))?;
+        let name = self.take(len)?;
+        self.pos += 1;
+        Ok(name)
+    }
+
+    /// Git's offset varint, used for v4 path prefix lengths
+    fn varint(&mut self) -> io::Result<usize> {
+        let mut byte = self.u8()?;
+        let mut value = usize::from(byte & 0x7f);
+        while byte & 0x80 != 0 {
+            byte = self.u8()?;
+            value = value
+                .checked_add(1)
+                .and_then(|v| v.checked_mul(128))
+                .ok_or_else(|| invalid("invalid path prefix"))?
+                + usize::from(byte & 0x7f);
+        }
+        Ok(value)
+    }
+}
+
+pub fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parse the entries of an index file, skipping those not in the worktree
+fn parse_index(bytes: &[u8], hash_len: usize) -> io::Result<Vec<IndexEntry>> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(4)? != b"DIRC" {
+        return Err(invalid("not a git index"));
+    }
+    let version = reader.u32()?;
+    if !(2..=4).contains(&version) {
+        return Err(invalid("unsupported index version"));
+    }
+    let count = reader.u32()?;
+
+    let mut entries: Vec<IndexEntry> = Vec::new();
+    let mut previous: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        let start = reader.pos;
+        reader.take(24)?; // ctime, mtime, dev, ino
+        let mode = reader.u32()?;
+        reader.take(12 + hash_len)?; // uid, gid, size, object name
+        let flags = reader.u16()?;
+        let extended_flags = if version >= 3 && flags & 0x4000 != 0 {
+            reader.u16()?
+        } else {
+            0
+        };
+
+        let name = if version == 4 {
+            let strip = reader.varint()?;
+            let kept = previous
+                .len()
+                .checked_sub(strip)
+                .ok_or_else(|| invalid("invalid path prefix"))?;
+            previous.truncate(kept);
+            previous.extend_from_slice(reader.name()?);
+            previous.clone()
+        } else {
+            let name = reader.name()?.to_vec();
+            // Entries are NUL-padded to a multiple of 8 bytes (the name's NUL included)
+            let len = reader.pos - 1 - start;
+            reader.take(((len + 8) & !7) - len - 1)?;
+            name
+        };
+
+        let kind = match mode >> 12 {
+            0o10 | 0o12 => EntryKind::File,
+            0o16 => EntryKind::Gitlink,
+            _ => EntryKind::SparseDir,
+        };
+        if extended_flags & SKIP_WORKTREE != 0 || kind == EntryKind::SparseDir {
+            continue;
+        }
+        let path = path_from_bytes(&name);
+        // Conflicts list a path once per stage
+        if entries.last().is_some_and(|last| last.path == path) {
+            continue;
+        }
+        entries.push(IndexEntry { path, kind });
+    }
+
+    // Extensions follow the entries, then the checksum
+    while reader.bytes.len().saturating_sub(reader.pos) > hash_len {
+        let signature = reader.take(4)?;
+        if signature == b"link" {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "split index is not supported",
+            ));
+        }
+        let size = reader.u32()?;
+        reader.take(size as usize)?;
+    }
+    Ok(entries)
+}
+
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FILE: u32 = 0o100_644;
+    const GITLINK: u32 = 0o160_000;
+    const SPARSE_DIR: u32 = 0o040_000;
+
+    /// Encode an index; entries are (path, mode, stage, skip-worktree)
+    fn encode_index(version: u32, entries: &[(&str, u32, u16, bool)]) -> Vec<u8> {
+        let mut out = b"DIRC".to_vec();
+        out.extend(version.to_be_bytes());
+        out.extend(u32::try_from(entries.len()).unwrap().to_be_bytes());
+        let mut previous = "";
+        for &(path, mode, stage, skip) in entries {
+            let start = out.len();
+            out.extend([0; 24]);
+            out.extend(mode.to_be_bytes());
+            out.extend([0; 32]);
+            let mut flags = (stage << 12) | u16::try_from(path.len()).unwrap();
+            if skip {
+                flags |= 0x4000;
+            }
+            out.extend(flags.to_be_bytes());
+            if skip {
+                out.extend(SKIP_WORKTREE.to_be_bytes());
+            }
+            if version == 4 {
+                let common = previous
+                    .bytes()
+                    .zip(path.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                out.push(u8::try_from(previous.len() - common).unwrap());
+                out.extend(&path.as_bytes()[common..]);
+                out.push(0);
+            } else {
+                out.extend(path.as_bytes());
+                let len = out.len() - start;
+                out.resize(start + ((len + 8) & !7), 0);
+            }
+            previous = path;
+        }
+        out.extend([0; 20]);
+        out
+    }
+
+    fn write_worktree(dir: &Path, entries: &[(&str, u32, u16, bool)]) {
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".git/index"), encode_index(3, entries)).unwrap();
+    }
+
+    #[test]
+    fn test_parse_index_versions() {
+        let entries = [
+            ("Cargo.toml", FILE, 0, false),
+            ("src/lib.rs", FILE, 0, false),
+            ("src/lib.rs", FILE, 2, false),
+            ("src/main.rs", FILE, 0, false),
+        ];
+        let expected: Vec<PathBuf> = ["Cargo.toml", "src/lib.rs", "src/main.rs"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        for version in [2, 4] {
+            let parsed = parse_index(&encode_index(version, &entries), 20).unwrap();
+            let paths: Vec<PathBuf> = parsed.into_iter().map(|e| e.path).collect();
+            assert_eq!(paths, expected, "version {version}");
+        }
+
+        assert!(parse_index(b"DIRC\0\0\0\x09", 20).is_err());
+        assert!(parse_index(&encode_index(2, &entries)[..60], 20).is_err());
+
+        // Cut inside the padding after the last entry's name
+        let index = encode_index(2, &[("a.rs", FILE, 0, false)]);
+        let cut = 12 + 62 + "a.rs".len() + 1;
+        assert!(parse_index(&index[..cut], 20).is_err());
+    }
+
+    #[test]
+    fn test_sparse_checkout_and_submodules() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        write_worktree(
+            repo,
+            &[
+                ("Cargo.toml", FILE, 0, false),
+                ("docs/guide.md", FILE, 0, true),
+                ("legacy/", SPARSE_DIR, 0, true),
+                ("src/main.rs", FILE, 0, false),
+                ("vendor/lib", GITLINK, 0, false),
+                ("vendor/missing", GITLINK, 0, false),
+            ],
+        );
+        // A checked-out submodule whose git directory lives in the parent
+        let submodule = repo.join("vendor/lib");
+        fs::create_dir_all(&submodule).unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+        fs::create_dir_all(repo.join(".git/modules/lib")).unwrap();
+        fs::write(
+            repo.join(".git/modules/lib/index"),
+            encode_index(2, &[("package.json", FILE, 0, false)]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            git_tracked_files(repo).unwrap(),
+            vec![
+                repo.join("Cargo.toml"),
+                repo.join("src/main.rs"),
+                repo.join("vendor/lib/package.json"),
+            ]
+        );
+        // From a subdirectory, only its files, relative to it
+        assert_eq!(
+            git_tracked_files(&repo.join("vendor")).unwrap(),
+            vec![repo.join("vendor/lib/package.json")]
+        );
+        assert!(matches!(
+            git_tracked_files(&repo.join("nope")),
+            Err(RootDetectionError::GitIndexRead { .. })
+        ));
+    }
+}
@@ -0,0 +1,936 @@
+//! Paths changed between two git trees, read straight from the object
+//! database.
+//!
+//! Revisions are object names, full or abbreviated to a unique prefix of at
+//! least four hex digits, or ref names (`HEAD`, `main`, `origin/main`,
+//! `v1.2`, `refs/...`), optionally followed by `^` or `~N`.
+//! [`git_merge_base`] finds the commit to diff `A...B` ranges from.
+//! Objects are read from loose files and packs, including deltas; alternates
+//! are not followed. A changed submodule is reported as its gitlink path.
+
+use crate::git_index::{common_dir, git_dir, hash_len, invalid, locate_worktree, path_from_bytes};
+use crate::RootDetectionError;
+use flate2::read::ZlibDecoder;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Mode of a tree entry for a directory
+const TREE_MODE: &str = "40000";
+/// Nesting limit for delta chains and symbolic refs
+const MAX_DEPTH: usize = 64;
+
+/// Paths whose contents differ between revisions `from` and `to`, limited to
+/// `dir`, which may be anywhere inside a worktree.
+///
+/// Added, modified and deleted files are all included. Paths are `dir` joined
+/// with each file's path relative to `dir`, sorted.
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::UnknownRevision`] if a revision cannot be
+/// resolved and [`RootDetectionError::GitObjectRead`] if the repository or
+/// an object cannot be read.
+pub fn git_changed_files(
+    dir: &Path,
+    from: &str,
+    to: &str,
+) -> Result<Vec<PathBuf>, RootDetectionError> {
+    let (repo, prefix) = Repository::locate(dir)?;
+    let from_tree = repo.resolve_tree(from)?;
+    let to_tree = repo.resolve_tree(to)?;
+    let mut changed = BTreeSet::new();
+    repo.diff_trees(
+        Some(&from_tree),
+        Some(&to_tree),
+        Path::new(""),
+        &mut changed,
+    )
+    .map_err(|source| repo.read_error(source))?;
+
+    Ok(changed
+        .iter()
+        .filter_map(|path| path.strip_prefix(&prefix).ok())
+        .map(|relative| dir.join(relative))
+        .collect())
+}
+
+/// Full object name of a common ancestor of revisions `a` and `b`, for
+/// diffing `A...B` ranges the way git does. When there are several, the one
+/// found first walking back from both sides at once is taken.
+///
+/// # Errors
+///
+/// Returns [`RootDetectionError::UnknownRevision`] if a revision cannot be
+/// resolved, [`RootDetectionError::NoMergeBase`] if the histories are
+/// unrelated and [`RootDetectionError::GitObjectRead`] if the repository or
+/// an object cannot be read.
+pub fn git_merge_base(dir: &Path, a: &str, b: &str) -> Result<String, RootDetectionError> {
+    let (repo, _) = Repository::locate(dir)?;
+    let a_commit = repo.peel(repo.resolve_object(a)?, Kind::Commit)?;
+    let b_commit = repo.peel(repo.resolve_object(b)?, Kind::Commit)?;
+    repo.merge_base(a_commit, b_commit)?
+        .map(|id| encode_hex(&id))
+        .ok_or_else(|| RootDetectionError::NoMergeBase(a.to_string(), b.to_string()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl Kind {
+    fn from_name(name: &[u8]) -> io::Result<Self> {
+        match name {
+            b"commit" => Ok(Self::Commit),
+            b"tree" => Ok(Self::Tree),
+            b"blob" => Ok(Self::Blob),
+            b"tag" => Ok(Self::Tag),
+            _ => Err(invalid("unknown object type")),
+        }
+    }
+
+    fn from_pack_type(code: u8) -> io::Result<Self> {
+        match code {
+            1 => Ok(Self::Commit),
+            2 => Ok(Self::Tree),
+            3 => Ok(Self::Blob),
+            4 => Ok(Self::Tag),
+            _ => Err(invalid("unknown packed object type")),
+        }
+    }
+}
+
+type ObjectId = Vec<u8>;
+
+/// A pack's `.idx` file, kept in memory, and the path of its `.pack`
+struct PackIndex {
+    index: Vec<u8>,
+    pack: PathBuf,
+}
+
+impl PackIndex {
+    /// Size of a version 2 index without large offsets, for its object count
+    const fn min_len(index: &[u8], hash_len: usize) -> usize {
+        let count = u32::from_be_bytes([index[1028], index[1029], index[1030], index[1031]]);
+        8 + 256 * 4 + count as usize * (hash_len + 8) + 2 * hash_len
+    }
+
+    /// Number of objects whose name's first byte is at most `byte`
+    fn fanout(&self, byte: usize) -> usize {
+        let at = 8 + byte * 4;
+        let idx = &self.index;
+        u32::from_be_bytes([idx[at], idx[at + 1], idx[at + 2], idx[at + 3]]) as usize
+    }
+
+    /// Positions of the names starting with byte `first`
+    fn names_from(&self, first: u8) -> std::ops::Range<usize> {
+        let count = self.fanout(255);
+        let first = usize::from(first);
+        let low = if first == 0 {
+            0
+        } else {
+            self.fanout(first - 1)
+        };
+        low.min(count)..self.fanout(first).min(count)
+    }
+
+    fn name(&self, i: usize, hash_len: usize) -> &[u8] {
+        let names = 8 + 256 * 4;
+        &self.index[names + i * hash_len..names + (i + 1) * hash_len]
+    }
+
+    /// Offset of `id` in the pack (version 2 index)
+    fn find(&self, id: &[u8]) -> Option<u64> {
+        let idx = &self.index;
+        let hash_len = id.len();
+        let count = self.fanout(255);
+        let names = self.names_from(*id.first()?);
+        let (mut low, mut high) = (names.start, names.end);
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.name(mid, hash_len).cmp(id) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    let offsets = 8 + 256 * 4 + count * (hash_len + 4);
+                    let at = offsets + mid * 4;
+                    let small =
+                        u32::from_be_bytes([idx[at], idx[at + 1], idx[at + 2], idx[at + 3]]);
+                    if small & 0x8000_0000 == 0 {
+                        return Some(u64::from(small));
+                    }
+                    let at = offsets + count * 4 + (small & 0x7fff_ffff) as usize * 8;
+                    let mut large = [0; 8];
+                    large.copy_from_slice(idx.get(at..at + 8)?);
+                    return Some(u64::from_be_bytes(large));
+                }
+            }
+        }
+        None
+    }
+
+    /// Names of the objects in the pack starting with the hex digits `prefix`
+    fn with_prefix(&self, prefix: &str, hash_len: usize) -> Vec<ObjectId> {
+        let Some(first) = prefix
+            .get(..2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        else {
+            return Vec::new();
+        };
+        self.names_from(first)
+            .map(|i| self.name(i, hash_len))
+            .filter(|name| encode_hex(name).starts_with(prefix))
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+}
+
+struct Repository {
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+    hash_len: usize,
+    packs: Vec<PackIndex>,
+}
+
+impl Repository {
+    /// Repository of the worktree enclosing `dir`, and `dir`'s path within it
+    fn locate(dir: &Path) -> Result<(Self, PathBuf), RootDetectionError> {
+        let (worktree, prefix) = locate_worktree(dir)?;
+        let dot_git = worktree.join(".git");
+        let git_dir = git_dir(&dot_git).map_err(|source| RootDetectionError::GitObjectRead {
+            path: dot_git,
+            source,
+        })?;
+        Ok((Self::open(git_dir), prefix))
+    }
+
+    fn open(git_dir: PathBuf) -> Self {
+        let common_dir = common_dir(&git_dir);
+        let hash_len = hash_len(&git_dir);
+        let pack_dir = common_dir.join("objects/pack");
+        let packs = fs::read_dir(pack_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "idx"))
+            .filter_map(|path| {
+                let index = fs::read(&path).ok()?;
+                // Version 2 only: magic, version, fanout
+                let valid = index.len() > 8 + 256 * 4
+                    && index.starts_with(b"\xfftOc")
+                    && index[4..8] == [0, 0, 0, 2]
+                    && index.len() >= PackIndex::min_len(&index, hash_len);
+                valid.then(|| PackIndex {
+                    index,
+                    pack: path.with_extension("pack"),
+                })
+            })
+            .collect();
+        Self {
+            git_dir,
+            common_dir,
+            hash_len,
+            packs,
+        }
+    }
+
+    fn read_error(&self, source: io::Error) -> RootDetectionError {
+        RootDetectionError::GitObjectRead {
+            path: self.common_dir.clone(),
+            source,
+        }
+    }
+
+    /// Tree named by a revision, peeling tags and commits
+    fn resolve_tree(&self, revision: &str) -> Result<ObjectId, RootDetectionError> {
+        self.peel(self.resolve_object(revision)?, Kind::Tree)
+    }
+
+    /// Object named by a revision, after following its `~N` / `^` suffixes
+    fn resolve_object(&self, revision: &str) -> Result<ObjectId, RootDetectionError> {
+        let unknown = || RootDetectionError::UnknownRevision(revision.to_string());
+        let (name, generations) = split_ancestry(revision).ok_or_else(unknown)?;
+        let mut id = self.resolve_name(name).ok_or_else(unknown)?;
+
+        for _ in 0..generations {
+            let commit = self.peel(id, Kind::Commit)?;
+            id = self
+                .parents(&commit)?
+                .into_iter()
+                .next()
+                .ok_or_else(unknown)?;
+        }
+        Ok(id)
+    }
+
+    /// Parents of a commit, first parent first
+    fn parents(&self, commit: &[u8]) -> Result<Vec<ObjectId>, RootDetectionError> {
+        let (_, data) = self
+            .read_object(commit)
+            .map_err(|source| self.read_error(source))?;
+        Ok(header_ids(&data, b"parent ", self.hash_len).collect())
+    }
+
+    /// First commit reached from both `a` and `b`, walking their histories
+    /// breadth-first in turn
+    fn merge_base(&self, a: ObjectId, b: ObjectId) -> Result<Option<ObjectId>, RootDetectionError> {
+        let mut seen = [HashSet::new(), HashSet::new()];
+        let mut queues = [VecDeque::from([a]), VecDeque::from([b])];
+        while queues.iter().any(|queue| !queue.is_empty()) {
+            for side in 0..2 {
+                let Some(id) = queues[side].pop_front() else {
+                    continue;
+                };
+                if seen[1 - side].contains(&id) {
+                    return Ok(Some(id));
+                }
+                if seen[side].insert(id.clone()) {
+                    let parents = self.parents(&id)?;
+                    queues[side].extend(parents);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Follow tags, and commits when `target` is a tree, to an object of
+    /// kind `target`
+    fn peel(&self, mut id: ObjectId, target: Kind) -> Result<ObjectId, RootDetectionError> {
+        for _ in 0..MAX_DEPTH {
+            let (kind, data) = self
+                .read_object(&id)
+                .map_err(|source| self.read_error(source))?;
+            let next = match kind {
+                kind if kind == target => return Ok(id),
+                Kind::Tag => header_id(&data, b"object ", self.hash_len),
+                Kind::Commit if target == Kind::Tree => header_id(&data, b"tree ", self.hash_len),
+                _ => return Err(self.read_error(invalid("revision is not a commit"))),
+            };
+            id = next.ok_or_else(|| self.read_error(invalid("invalid commit or tag")))?;
+        }
+        Err(self.read_error(invalid("too many nested tags")))
+    }
+
+    /// Object name for a full hex name, a ref or an abbreviated name, tried
+    /// in git's order
+    fn resolve_name(&self, name: &str) -> Option<ObjectId> {
+        if let Some(id) = self.full_id(name) {
+            return Some(id);
+        }
+        [
+            name.to_string(),
+            format!("refs/{name}"),
+            format!("refs/tags/{name}"),
+            format!("refs/heads/{name}"),
+            format!("refs/remotes/{name}"),
+            format!("refs/remotes/{name}/HEAD"),
+        ]
+        .iter()
+        .find_map(|candidate| self.resolve_ref(candidate, 0))
+        .or_else(|| self.find_abbreviated(name))
+    }
+
+    /// `hex` as an object name of this repository's hash length
+    fn full_id(&self, hex: &str) -> Option<ObjectId> {
+        decode_hex(hex).filter(|id| id.len() == self.hash_len)
+    }
+
+    /// The only object, loose or packed, whose name starts with `prefix`
+    /// (at least 4 hex digits)
+    fn find_abbreviated(&self, prefix: &str) -> Option<ObjectId> {
+        let prefix = prefix.to_ascii_lowercase();
+        let valid = (4..self.hash_len * 2).contains(&prefix.len())
+            && prefix.bytes().all(|b| b.is_ascii_hexdigit());
+        if !valid {
+            return None;
+        }
+
+        let (dir, rest) = prefix.split_at(2);
+        let mut found: BTreeSet<ObjectId> = fs::read_dir(self.common_dir.join("objects").join(dir))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.starts_with(rest))
+            .filter_map(|name| self.full_id(&format!("{dir}{name}")))
+            .collect();
+        for pack in &self.packs {
+            found.extend(pack.with_prefix(&prefix, self.hash_len));
+        }
+
+        let mut found = found.into_iter();
+        let id = found.next()?;
+        found.next().is_none().then_some(id)
+    }
+
+    fn resolve_ref(&self, name: &str, depth: usize) -> Option<ObjectId> {
+        if depth > MAX_DEPTH || name.split('/').any(|part| part.is_empty() || part == "..") {
+            return None;
+        }
+        // Per-worktree refs like HEAD live in the git dir, shared ones in the common dir
+        let loose = [&self.git_dir, &self.common_dir]
+            .iter()
+            .find_map(|dir| fs::read_to_string(dir.join(name)).ok());
+        if let Some(contents) = loose {
+            let contents = contents.trim();
+            return contents.strip_prefix("ref:").map_or_else(
+                || self.full_id(contents),
+                |target| self.resolve_ref(target.trim(), depth + 1),
+            );
+        }
+        let packed = fs::read_to_string(self.common_dir.join("packed-refs")).ok()?;
+        packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .find_map(|line| {
+                let (id, ref_name) = line.split_once(' ')?;
+                (ref_name == name).then(|| self.full_id(id)).flatten()
+            })
+    }
+
+    fn read_object(&self, id: &[u8]) -> io::Result<(Kind, Vec<u8>)> {
+        if id.len() != self.hash_len {
+            return Err(invalid("invalid object name"));
+        }
+        let hex = encode_hex(id);
+        let loose = self
+            .common_dir
+            .join("objects")
+            .join(&hex[..2])
+            .join(&hex[2..]);
+        match File::open(loose) {
+            Ok(file) => {
+                let mut data = Vec::new();
+                ZlibDecoder::new(BufReader::new(file)).read_to_end(&mut data)?;
+                let nul = data
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(|| invalid("invalid object header"))?;
+                let kind_name = data[..nul].split(|&b| b == b' ').next().unwrap_or_default();
+                let kind = Kind::from_name(kind_name)?;
+                data.drain(..=nul);
+                Ok((kind, data))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => self
+                .packs
+                .iter()
+                .find_map(|pack| Some((pack, pack.find(id)?)))
+                .map_or_else(
+                    || {
+                        Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("object {hex} not found"),
+                        ))
+                    },
+                    |(pack, offset)| self.read_packed(&pack.pack, offset, 0),
+                ),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_packed(&self, pack: &Path, offset: u64, depth: usize) -> io::Result<(Kind, Vec<u8>)> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("delta chain too long"));
+        }
+        let mut file = BufReader::new(File::open(pack)?);
+        file.seek(SeekFrom::Start(offset))?;
+
+        // Type and inflated size, little-endian base-128
+        let mut byte = read_byte(&mut file)?;
+        let code = (byte >> 4) & 0x7;
+        let mut size = u64::from(byte & 0x0f);
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_byte(&mut file)?;
+            size |= u64::from(byte & 0x7f).checked_shl(shift).unwrap_or(0);
+            shift += 7;
+        }
+
+        let base = match code {
+            6 => {
+                // Offset delta: big-endian base-128 with a +1 per continuation
+                let mut byte = read_byte(&mut file)?;
+                let mut distance = u64::from(byte & 0x7f);
+                while byte & 0x80 != 0 {
+                    byte = read_byte(&mut file)?;
+                    distance = ((distance + 1) << 7) | u64::from(byte & 0x7f);
+                }
+                let base_offset = offset
+                    .checked_sub(distance)
+                    .ok_or_else(|| invalid("invalid delta offset"))?;
+                Some(self.read_packed(pack, base_offset, depth + 1)?)
+            }
+            7 => {
+                let mut base_id = vec![0; self.hash_len];
+                file.read_exact(&mut base_id)?;
+                Some(self.read_object(&base_id)?)
+            }
+            _ => None,
+        };
+
+        let mut data = Vec::new();
+        ZlibDecoder::new(file).take(size).read_to_end(&mut data)?;
+        match base {
+            Some((kind, base)) => Ok((kind, apply_delta(&base, &data)?)),
+            None => Ok((Kind::from_pack_type(code)?, data)),
+        }
+    }
+
+    /// Entries of a tree: name to (mode, object name)
+    fn read_tree(&self, id: &[u8]) -> io::Result<BTreeMap<Vec<u8>, TreeEntry>> {
+        let (kind, data) = self.read_object(id)?;
+        if kind != Kind::Tree {
+            return Err(invalid("expected a tree"));
+        }
+        let mut entries = BTreeMap::new();
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let space = rest.iter().position(|&b| b == b' ');
+            let nul = rest.iter().position(|&b| b == 0);
+            let (Some(space), Some(nul)) = (space, nul) else {
+                return Err(invalid("invalid tree entry"));
+            };
+            let end = nul + 1 + self.hash_len;
+            if space > nul || end > rest.len() {
+                return Err(invalid("invalid tree entry"));
+            }
+            let mode = String::from_utf8_lossy(&rest[..space]).into_owned();
+            entries.insert(
+                rest[space + 1..nul].to_vec(),
+                (mode, rest[nul + 1..end].to_vec()),
+            );
+            rest = &rest[end..];
+        }
+        Ok(entries)
+    }
+
+    /// Add every path that differs between two trees (either may be absent)
+    fn diff_trees(
+        &self,
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+        base: &Path,
+        changed: &mut BTreeSet<PathBuf>,
+    ) -> io::Result<()> {
+        let read =
+            |id: Option<&[u8]>| id.map_or_else(|| Ok(BTreeMap::new()), |id| self.read_tree(id));
+        let old = read(old)?;
+        let new = read(new)?;
+        let names: BTreeSet<&Vec<u8>> = old.keys().chain(new.keys()).collect();
+
+        for name in names {
+            let before = old.get(name);
+            let after = new.get(name);
+            if before == after {
+                continue;
+            }
+            let path = base.join(path_from_bytes(name));
+            let (old_tree, new_tree) = (subtree(before), subtree(after));
+            if old_tree.is_some() || new_tree.is_some() {
+                self.diff_trees(old_tree, new_tree, &path, changed)?;
+            }
+            // A file (or gitlink) on either side that is not identical on the other
+            if is_leaf(before) || is_leaf(after) {
+                changed.insert(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+type TreeEntry = (String, ObjectId);
+
+/// Object name of a tree entry that is a directory
+fn subtree(entry: Option<&TreeEntry>) -> Option<&[u8]> {
+    entry
+        .filter(|(mode, _)| mode == TREE_MODE)
+        .map(|(_, id)| id.as_slice())
+}
+
+fn is_leaf(entry: Option<&TreeEntry>) -> bool {
+    entry.is_some_and(|(mode, _)| mode != TREE_MODE)
+}
+
+/// Split `rev~N` / `rev^` suffixes into a name and a number of first parents
+fn split_ancestry(revision: &str) -> Option<(&str, usize)> {
+    let end = revision.find(['~', '^']).unwrap_or(revision.len());
+    let (name, mut suffix) = revision.split_at(end);
+    let mut generations = 0;
+    while !suffix.is_empty() {
+        let kind = suffix.as_bytes()[0];
+        suffix = &suffix[1..];
+        let digits = suffix.find(['~', '^']).unwrap_or(suffix.len());
+        let count = match (&suffix[..digits], kind) {
+            ("", _) | ("1", b'^') => 1,
+            (n, b'~') => n.parse().ok()?,
+            _ => return None, // `^N` for other parents is not supported
+        };
+        generations += count;
+        suffix = &suffix[digits..];
+    }
+    (!name.is_empty()).then_some((name, generations))
+}
+
+/// Object name from the first `<prefix><hex>` line of a commit or tag header
+fn header_id(data: &[u8], prefix: &[u8], hash_len: usize) -> Option<ObjectId> {
+    header_ids(data, prefix, hash_len).next()
+}
+
+/// Object names from every `<prefix><hex>` line of a commit or tag header
+fn header_ids<'a>(
+    data: &'a [u8],
+    prefix: &'a [u8],
+    hash_len: usize,
+) -> impl Iterator<Item = ObjectId> + 'a {
+    let header_end = data
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .unwrap_or(data.len());
+    data[..header_end]
+        .split(|&b| b == b'\n')
+        .filter_map(move |line| line.strip_prefix(prefix))
+        .filter_map(|hex| decode_hex(std::str::from_utf8(hex).ok()?))
+        .filter(move |id| id.len() == hash_len)
+}
+
+/// Rebuild an object from its delta base and delta instructions
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut pos = 0;
+    let varint = |pos: &mut usize| -> io::Result<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = *delta.get(*pos).ok_or_else(|| invalid("truncated delta"))?;
+            *pos += 1;
+            value |= usize::from(byte & 0x7f).checked_shl(shift).unwrap_or(0);
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    };
+    let base_size = varint(&mut pos)?;
+    let result_size = varint(&mut pos)?;
+    if base_size != base.len() {
+        return Err(invalid("delta base size mismatch"));
+    }
+
+    let mut out = Vec::with_capacity(result_size);
+    while let Some(&op) = delta.get(pos) {
+        pos += 1;
+        if op & 0x80 != 0 {
+            // Copy from base: offset and size bytes present per bit
+            let mut fields = [0usize; 2];
+            let mut bit = 0;
+            for (field, width) in fields.iter_mut().zip([4, 3]) {
+                for i in 0..width {
+                    if op & (1 << bit) != 0 {
+                        let byte = *delta.get(pos).ok_or_else(|| invalid("truncated delta"))?;
+                        *field |= usize::from(byte) << (8 * i);
+                        pos += 1;
+                    }
+                    bit += 1;
+                }
+            }
+            let [offset, size] = fields;
+            let size = if size == 0 { 0x10000 } else { size };
+            let chunk = offset
+                .checked_add(size)
+                .and_then(|end| base.get(offset..end))
+                .ok_or_else(|| invalid("delta copy out of range"))?;
+            out.extend_from_slice(chunk);
+        } else if op != 0 {
+            let chunk = delta
+                .get(pos..pos + usize::from(op))
+                .ok_or_else(|| invalid("truncated delta"))?;
+            out.extend_from_slice(chunk);
+            pos += usize::from(op);
+        } else {
+            return Err(invalid("invalid delta instruction"));
+        }
+    }
+    if out.len() != result_size {
+        return Err(invalid("delta result size mismatch"));
+    }
+    Ok(out)
+}
+
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn decode_hex(hex: &str) -> Option<ObjectId> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Loose object store with made-up object names (nothing checks hashes)
+    struct Store {
+        git_dir: PathBuf,
+        next: u8,
+    }
+
+    impl Store {
+        fn write(&mut self, kind: &str, data: &[u8]) -> ObjectId {
+            self.next += 1;
+            let id = vec![self.next; 20];
+            let hex = encode_hex(&id);
+            let dir = self.git_dir.join("objects").join(&hex[..2]);
+            fs::create_dir_all(&dir).unwrap();
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            write!(encoder, "{kind} {}\0", data.len()).unwrap();
+            encoder.write_all(data).unwrap();
+            fs::write(dir.join(&hex[2..]), encoder.finish().unwrap()).unwrap();
+            id
+        }
+
+        fn tree(&mut self, entries: &[(&str, &str, &ObjectId)]) -> ObjectId {
+            let mut data = Vec::new();
+            for (mode, name, id) in entries {
+                data.extend(format!("{mode} {name}\0").as_bytes());
+                data.extend(id.iter());
+            }
+            self.write("tree", &data)
+        }
+
+        fn commit(&mut self, tree: &ObjectId, parent: Option<&ObjectId>) -> ObjectId {
+            let mut data = format!("tree {}\n", encode_hex(tree));
+            if let Some(parent) = parent {
+                data += &format!("parent {}\n", encode_hex(parent));
+            }
+            data += "author a <a@b> 0 +0000\n\nmessage\n";
+            self.write("commit", data.as_bytes())
+        }
+    }
+
+    #[test]
+    fn test_changed_files_between_revisions() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        let git_dir = repo.join(".git");
+        let mut store = Store {
+            git_dir: git_dir.clone(),
+            next: 0,
+        };
+
+        let old_lib = store.write("blob", b"old");
+        let new_lib = store.write("blob", b"new");
+        let readme = store.write("blob", b"readme");
+        let old_src = store.tree(&[("100644", "lib.rs", &old_lib)]);
+        let new_src = store.tree(&[("100644", "lib.rs", &new_lib)]);
+        let gone = store.tree(&[("100644", "a.py", &readme), ("100644", "b.py", &readme)]);
+        let first_tree = store.tree(&[
+            ("100644", "README", &readme),
+            ("40000", "gone", &gone),
+            ("40000", "src", &old_src),
+        ]);
+        let second_tree = store.tree(&[
+            ("100644", "README", &readme),
+            ("40000", "src", &new_src),
+            ("100644", "gone", &readme),
+        ]);
+        let first = store.commit(&first_tree, None);
+        let second = store.commit(&second_tree, Some(&first));
+
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::create_dir_all(git_dir.join("refs/heads")).unwrap();
+        fs::write(git_dir.join("refs/heads/main"), encode_hex(&second)).unwrap();
+        fs::write(
+            git_dir.join("packed-refs"),
+            format!("# pack-refs\n{} refs/tags/v1\n", encode_hex(&first)),
+        )
+        .unwrap();
+
+        let expected = vec![
+            repo.join("gone"),
+            repo.join("gone/a.py"),
+            repo.join("gone/b.py"),
+            repo.join("src/lib.rs"),
+        ];
+        assert_eq!(git_changed_files(repo, "v1", "HEAD").unwrap(), expected);
+        assert_eq!(git_changed_files(repo, "main~1", "main").unwrap(), expected);
+        assert_eq!(
+            git_changed_files(repo, &encode_hex(&first), "HEAD^").unwrap(),
+            Vec::<PathBuf>::new()
+        );
+
+        fs::create_dir_all(repo.join("src")).unwrap();
+        assert_eq!(
+            git_changed_files(&repo.join("src"), "v1", "HEAD").unwrap(),
+            vec![repo.join("src/lib.rs")]
+        );
+        assert!(matches!(
+            git_changed_files(repo, "HEAD~2", "HEAD"),
+            Err(RootDetectionError::UnknownRevision(_))
+        ));
+
+        // Abbreviated names resolve while they are unique
+        let abbreviated = &encode_hex(&first)[..7];
+        assert_eq!(
+            git_changed_files(repo, abbreviated, "HEAD").unwrap(),
+            expected
+        );
+        let mut twin = encode_hex(&first);
+        twin.replace_range(39.., "f");
+        fs::copy(
+            git_dir.join("objects/09").join(&encode_hex(&first)[2..]),
+            git_dir.join("objects/09").join(&twin[2..]),
+        )
+        .unwrap();
+        assert!(matches!(
+            git_changed_files(repo, abbreviated, "HEAD"),
+            Err(RootDetectionError::UnknownRevision(_))
+        ));
+
+        // Truncated refs are unknown rather than short object names
+        fs::write(git_dir.join("refs/heads/empty"), "").unwrap();
+        fs::write(git_dir.join("refs/heads/short"), "0a\n").unwrap();
+        fs::write(git_dir.join("packed-refs"), "0a refs/tags/v2\n").unwrap();
+        for name in ["empty", "short", "v2"] {
+            assert!(matches!(
+                git_changed_files(repo, name, "HEAD"),
+                Err(RootDetectionError::UnknownRevision(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_merge_base() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path();
+        let git_dir = repo.join(".git");
+        let mut store = Store {
+            git_dir: git_dir.clone(),
+            next: 0,
+        };
+
+        // base -- main
+        //     \-- topic -- merge (of main)
+        let tree = store.tree(&[]);
+        let base = store.commit(&tree, None);
+        let main = store.commit(&tree, Some(&base));
+        let topic = store.commit(&tree, Some(&base));
+        let merge = store.write(
+            "commit",
+            format!(
+                "tree {}\nparent {}\nparent {}\n\nmerge\n",
+                encode_hex(&tree),
+                encode_hex(&topic),
+                encode_hex(&main)
+            )
+            .as_bytes(),
+        );
+        let orphan = store.commit(&tree, None);
+
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/topic\n").unwrap();
+        fs::create_dir_all(git_dir.join("refs/heads")).unwrap();
+        for (name, id) in [("main", &main), ("topic", &topic), ("merge", &merge)] {
+            fs::write(git_dir.join("refs/heads").join(name), encode_hex(id)).unwrap();
+        }
+
+        assert_eq!(
+            git_merge_base(repo, "main", "HEAD").unwrap(),
+            encode_hex(&base)
+        );
+        assert_eq!(
+            git_merge_base(repo, "HEAD", "main").unwrap(),
+            encode_hex(&base)
+        );
+        assert_eq!(
+            git_merge_base(repo, "main", "merge").unwrap(),
+            encode_hex(&main)
+        );
+        assert_eq!(
+            git_merge_base(repo, "topic", "topic").unwrap(),
+            encode_hex(&topic)
+        );
+        assert!(matches!(
+            git_merge_base(repo, "main", &encode_hex(&orphan)),
+            Err(RootDetectionError::NoMergeBase(..))
+        ));
+        assert!(matches!(
+            git_merge_base(repo, "main", "nope"),
+            Err(RootDetectionError::UnknownRevision(_))
+        ));
+    }
+
+    #[test]
+    fn test_pack_index_prefix_lookup() {
+        let names = [vec![0x12; 20], vec![0xab; 20], {
+            let mut id = vec![0xab; 20];
+            id[19] = 0xbb;
+            id
+        }];
+        let mut index = vec![0xff, b't', b'O', b'c', 0, 0, 0, 2];
+        for byte in 0..=255u8 {
+            let count = u32::try_from(names.iter().filter(|name| name[0] <= byte).count()).unwrap();
+            index.extend(count.to_be_bytes());
+        }
+        index.extend(names.concat());
+        index.extend(vec![0; names.len() * 4]);
+        for offset in [0u32, 100, 200] {
+            index.extend(offset.to_be_bytes());
+        }
+        let pack = PackIndex {
+            index,
+            pack: PathBuf::new(),
+        };
+
+        assert_eq!(pack.with_prefix("1212", 20), vec![names[0].clone()]);
+        assert_eq!(pack.with_prefix("abab", 20).len(), 2);
+        assert_eq!(
+            pack.with_prefix("abababababababababababababababababababa", 20),
+            vec![names[1].clone()]
+        );
+        assert!(pack.with_prefix("cd12", 20).is_empty());
+        assert_eq!(pack.find(&names[2]), Some(200));
+        assert_eq!(pack.find(&[]), None);
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let base = b"hello, world";
+        // Sizes 12 -> 11, copy base[7..12], insert "! ", copy base[0..4]
+        let delta = [12, 11, 0x91, 7, 5, 2, b'!', b' ', 0x90, 4];
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"world! hell");
+        assert!(apply_delta(base, &[13, 1, 1, b'x']).is_err());
+    }
+
+    #[test]
+    fn test_split_ancestry() {
+        assert_eq!(split_ancestry("HEAD"), Some(("HEAD", 0)));
+        assert_eq!(split_ancestry("main~3^"), Some(("main", 4)));
+        assert_eq!(split_ancestry("v1^1~"), Some(("v1", 2)));
+        assert_eq!(split_ancestry("HEAD^2"), None);
+        assert_eq!(split_ancestry("~1"), None);
+    }
+}
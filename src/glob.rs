@@ -0,0 +1,272 @@
+//! Minimal glob matcher for path patterns.
+//!
+//! Supports `*` (any run of characters within a component), `?` (one
+//! character), `[abc]` / `[a-z]` / `[!x]` classes, `\` escapes and `**` as a
+//! whole component (zero or more path components).
+
+/// A compiled glob pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<Segment>,
+    case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`: zero or more components
+    AnyComponents,
+    /// Component without wildcards
+    Literal(String),
+    /// Component with wildcards
+    Wildcard(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    AnyRun,
+    AnyChar,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    /// Compile a pattern; malformed classes are treated as literal text
+    #[must_use]
+    pub fn new(pattern: &str, case_insensitive: bool) -> Self {
+        let normalized = if case_insensitive {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        let segments = normalized
+            .split('/')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .map(compile_segment)
+            .collect();
+
+        Self {
+            pattern: pattern.to_string(),
+            segments,
+            case_insensitive,
+        }
+    }
+
+    /// Match a sequence of path components
+    #[must_use]
+    pub fn matches_components<S: AsRef<str>>(&self, components: &[S]) -> bool {
+        if self.case_insensitive {
+            let lowered: Vec<String> = components
+                .iter()
+                .map(|c| c.as_ref().to_lowercase())
+                .collect();
+            match_segments(&self.segments, &lowered)
+        } else {
+            match_segments(&self.segments, components)
+        }
+    }
+}
+
+/// Whether `pattern` uses any glob syntax (otherwise it is a plain name or path)
+#[must_use]
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '\\'])
+}
+
+fn compile_segment(segment: &str) -> Segment {
+    if segment == "**" {
+        return Segment::AnyComponents;
+    }
+
+    let chars: Vec<char> = segment.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '*' => {
+                if tokens.last() != Some(&Token::AnyRun) {
+                    tokens.push(Token::AnyRun);
+                }
+            }
+            '?' => tokens.push(Token::AnyChar),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                tokens.push(Token::Char(chars[i]));
+            }
+            '[' => {
+                if let Some((class, next)) = compile_class(&chars, i) {
+                    tokens.push(class);
+                    i = next;
+                    continue;
+                }
+                tokens.push(Token::Char('['));
+            }
+            _ => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+
+    if tokens.iter().all(|t| matches!(t, Token::Char(_))) {
+        Segment::Literal(
+            tokens
+                .into_iter()
+                .map(|t| match t {
+                    Token::Char(c) => c,
+                    _ => unreachable!(),
+                })
+                .collect(),
+        )
+    } else {
+        Segment::Wildcard(tokens)
+    }
+}
+
+/// Parse a `[...]` class starting at `start`; returns the token and the index after `]`
+fn compile_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&e| e != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+    None
+}
+
+fn match_segments<S: AsRef<str>>(segments: &[Segment], components: &[S]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((Segment::AnyComponents, rest)) => {
+            (0..=components.len()).any(|skip| match_segments(rest, &components[skip..]))
+        }
+        Some((segment, rest)) => components.split_first().is_some_and(|(first, tail)| {
+            match_segment(segment, first.as_ref()) && match_segments(rest, tail)
+        }),
+    }
+}
+
+fn match_segment(segment: &Segment, name: &str) -> bool {
+    match segment {
+        Segment::AnyComponents => true,
+        Segment::Literal(literal) => literal == name,
+        Segment::Wildcard(tokens) => {
+            let chars: Vec<char> = name.chars().collect();
+            match_tokens(tokens, &chars)
+        }
+    }
+}
+
+/// Iterative wildcard matching with single-star backtracking
+fn match_tokens(tokens: &[Token], name: &[char]) -> bool {
+    let (mut t, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match tokens.get(t) {
+            Some(Token::AnyRun) => {
+                backtrack = Some((t, n));
+                t += 1;
+            }
+            Some(token) if token_matches(token, name[n]) => {
+                t += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_t, star_n)) => {
+                    t = star_t + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_t, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens[t..].iter().all(|token| *token == Token::AnyRun)
+}
+
+fn token_matches(token: &Token, c: char) -> bool {
+    match token {
+        Token::Char(expected) => *expected == c,
+        Token::AnyRun | Token::AnyChar => true,
+        Token::Class { negated, ranges } => {
+            ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches_name(pattern: &str, name: &str) -> bool {
+        Glob::new(pattern, false).matches_components(&[name])
+    }
+
+    fn matches_path(glob: &Glob, path: &str) -> bool {
+        glob.matches_components(&path.split('/').collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_name_wildcards() {
+        assert!(matches_name("*.egg-info", "mypkg.egg-info"));
+        assert!(matches_name("*.egg-info", ".egg-info"));
+        assert!(!matches_name("*.egg-info", "egg-info"));
+        assert!(matches_name("test_?.py", "test_a.py"));
+        assert!(matches_name("*.[ch]pp", "main.cpp"));
+        assert!(!matches_name("*.[!c]pp", "main.cpp"));
+        assert!(matches_name("v[0-9]*", "v12"));
+        assert!(matches_name(r"\*", "*"));
+        assert!(!matches_name(r"\*", "a"));
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        let glob = Glob::new("crates/*", false);
+        assert!(matches_path(&glob, "crates/core"));
+        assert!(!matches_path(&glob, "crates/core/sub"));
+        assert!(!matches_path(&glob, "crates"));
+
+        let glob = Glob::new("docs/**/generated", false);
+        assert!(matches_path(&glob, "docs/generated"));
+        assert!(matches_path(&glob, "docs/a/b/generated"));
+        assert!(!matches_path(&glob, "src/generated"));
+
+        assert!(matches_path(
+            &Glob::new("./packages/*/", false),
+            "packages/api"
+        ));
+    }
+
+    #[test]
+    fn test_is_glob() {
+        assert!(is_glob("*.sln"));
+        assert!(is_glob("v[0-9]"));
+        assert!(!is_glob("node_modules"));
+        assert!(!is_glob("docs/generated"));
+    }
+
+    #[test]
+    fn test_case_folding() {
+        assert!(Glob::new("*.SLN", true).matches_components(&["App.sln"]));
+        assert!(!matches_name("*.SLN", "App.sln"));
+    }
+}
@@ -0,0 +1,368 @@
+//! Ignore files for traversal (`.gitignore`, `.git/info/exclude`, the global
+//! git excludes file and `.rootdetectignore`).
+//!
+//! Rules follow gitignore semantics: later rules override earlier ones, `!`
+//! re-includes, a trailing `/` only matches directories, and a pattern with a
+//! `/` elsewhere is anchored to the directory of its ignore file. Ignore files
+//! are scoped to their directory, and deeper files take precedence:
+//!
+//! ```text
+//! global excludes < .git/info/exclude < repo/.gitignore < repo/.rootdetectignore
+//!                 < repo/sub/.gitignore < repo/sub/.rootdetectignore < ...
+//! ```
+
+use crate::glob::Glob;
+use crate::pattern::path_components;
+use crate::{FileSystem, StdFs};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Tool-specific ignore file, read after `.gitignore` in the same directory
+pub const IGNORE_FILE_NAME: &str = ".rootdetectignore";
+
+/// Per-directory ignore files, in increasing order of precedence
+const DIR_IGNORE_FILES: &[&str] = &[".gitignore", IGNORE_FILE_NAME];
+
+/// One gitignore rule
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+    /// Match the path relative to the ignore file instead of just the name
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str, case_insensitive: bool) -> Option<Self> {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with('#') {
+            return None;
+        }
+
+        // Trailing spaces are ignored unless escaped
+        let mut line = line;
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        let (negated, line) = line
+            .strip_prefix('!')
+            .map_or((false, line), |rest| (true, rest));
+        let (dir_only, line) = line
+            .strip_suffix('/')
+            .map_or((false, line), |rest| (true, rest));
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            glob: Glob::new(line, case_insensitive),
+            negated,
+            dir_only,
+            anchored: line.contains('/'),
+        })
+    }
+
+    fn matches(&self, relative: &[&str], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.glob.matches_components(relative)
+        } else {
+            relative
+                .last()
+                .is_some_and(|name| self.glob.matches_components(&[name]))
+        }
+    }
+}
+
+/// Rules of one ignore file
+#[derive(Debug)]
+struct IgnoreFile {
+    /// Traversed path the rules apply below
+    root: PathBuf,
+    /// Components from the ignore file's directory down to `root`, for files
+    /// read from ancestors of the traversal start
+    prefix: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    fn load(
+        fs: &dyn FileSystem,
+        path: &Path,
+        root: PathBuf,
+        prefix: Vec<String>,
+        case_insensitive: bool,
+    ) -> Option<Self> {
+        let contents = fs.read_to_string(path).ok()?;
+        let rules: Vec<Rule> = contents
+            .lines()
+            .filter_map(|line| Rule::parse(line, case_insensitive))
+            .collect();
+        (!rules.is_empty()).then_some(Self {
+            root,
+            prefix,
+            rules,
+        })
+    }
+
+    /// `Some(ignored)` if a rule decides `path`, `None` if no rule matches
+    fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let below = path.strip_prefix(&self.root).ok()?;
+        let relative: Vec<&str> = self
+            .prefix
+            .iter()
+            .map(String::as_str)
+            .chain(path_components(below))
+            .collect();
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&relative, is_dir))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Ignore files in effect for a directory during traversal
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreStack {
+    files: Vec<Arc<IgnoreFile>>,
+    case_insensitive: bool,
+}
+
+impl IgnoreStack {
+    /// Rules in effect at the traversal start: `global_excludes`,
+    /// `.git/info/exclude` and ignore files between the repository root and
+    /// `start` (exclusive; `start`'s own files are added by [`IgnoreStack::enter`])
+    pub fn for_start(
+        fs: &dyn FileSystem,
+        start: &Path,
+        case_insensitive: bool,
+        global_excludes: Option<&Path>,
+    ) -> Self {
+        let mut stack = Self {
+            files: Vec::new(),
+            case_insensitive,
+        };
+
+        let canonical = fs
+            .canonicalize(start)
+            .unwrap_or_else(|_| start.to_path_buf());
+        let repo_root = canonical
+            .ancestors()
+            .find(|dir| fs.exists(&dir.join(".git")))
+            .map(Path::to_path_buf);
+        let prefix_from = |dir: &Path| -> Vec<String> {
+            canonical
+                .strip_prefix(dir)
+                .map(|rest| {
+                    path_components(rest)
+                        .into_iter()
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let base = repo_root.clone().unwrap_or_else(|| canonical.clone());
+
+        // The global excludes file belongs to the user, not the tree: always on disk
+        if let Some(global) = global_excludes {
+            stack.push(&StdFs, global, start.to_path_buf(), prefix_from(&base));
+        }
+
+        let mut sources: Vec<(PathBuf, Vec<String>)> = Vec::new();
+        if let Some(root) = &repo_root {
+            sources.push((root.join(".git/info/exclude"), prefix_from(root)));
+
+            // Ancestors of the start directory, outermost first
+            let mut ancestors: Vec<&Path> = canonical
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(root))
+                .collect();
+            ancestors.reverse();
+            for dir in ancestors {
+                for name in DIR_IGNORE_FILES {
+                    sources.push((dir.join(name), prefix_from(dir)));
+                }
+            }
+        }
+
+        for (path, prefix) in sources {
+            stack.push(fs, &path, start.to_path_buf(), prefix);
+        }
+        stack
+    }
+
+    /// Rules in effect inside `dir`: these plus `dir`'s own ignore files
+    pub fn enter(&self, fs: &dyn FileSystem, dir: &Path) -> Self {
+        let mut stack = self.clone();
+        for name in DIR_IGNORE_FILES {
+            stack.push(fs, &dir.join(name), dir.to_path_buf(), Vec::new());
+        }
+        stack
+    }
+
+    fn push(&mut self, fs: &dyn FileSystem, path: &Path, root: PathBuf, prefix: Vec<String>) {
+        if let Some(file) = IgnoreFile::load(fs, path, root, prefix, self.case_insensitive) {
+            self.files.push(Arc::new(file));
+        }
+    }
+
+    /// Whether `path` is ignored; `.git` directories always are
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if is_dir && path.file_name().is_some_and(|n| n == ".git") {
+            return true;
+        }
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| file.decide(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+/// The user's global git excludes file: `core.excludesFile` from their git
+/// config, or git's default `$XDG_CONFIG_HOME/git/ignore`, if it exists
+///
+/// Traversals only read it when it is passed as
+/// [`crate::TraversalOptions::global_excludes_file`].
+#[must_use]
+pub fn global_excludes_file() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|h| h.join(".config")));
+
+    // ~/.gitconfig is read after the XDG file, so it wins
+    let configured = [
+        xdg_config.as_ref().map(|d| d.join("git/config")),
+        home.as_ref().map(|h| h.join(".gitconfig")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| std::fs::read_to_string(path).ok())
+    .filter_map(|contents| core_excludes_file(&contents))
+    .last();
+
+    let path = match configured {
+        Some(value) => match (value.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(value),
+        },
+        None => xdg_config?.join("git/ignore"),
+    };
+    path.is_file().then_some(path)
+}
+
+/// Value of `core.excludesFile` in a git config file
+fn core_excludes_file(contents: &str) -> Option<String> {
+    let mut in_core = false;
+    let mut value = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_core = line
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .trim()
+                .eq_ignore_ascii_case("core");
+        } else if in_core {
+            if let Some((key, val)) = line.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("excludesfile") {
+                    value = Some(val.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn rules(lines: &str) -> IgnoreFile {
+        IgnoreFile {
+            root: PathBuf::from("/repo"),
+            prefix: Vec::new(),
+            rules: lines
+                .lines()
+                .filter_map(|l| Rule::parse(l, false))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_gitignore_rules() {
+        let file =
+            rules("# comment\n*.log\n!keep.log\nbuild/\n/docs/gen\nsrc/**/tmp\n\\#notes  \n");
+        let ignored = |path: &str, is_dir| file.decide(Path::new(path), is_dir);
+
+        assert_eq!(ignored("/repo/a/debug.log", false), Some(true));
+        assert_eq!(ignored("/repo/a/keep.log", false), Some(false));
+        assert_eq!(ignored("/repo/x/build", true), Some(true));
+        assert_eq!(ignored("/repo/x/build", false), None);
+        assert_eq!(ignored("/repo/docs/gen", true), Some(true));
+        assert_eq!(ignored("/repo/sub/docs/gen", true), None);
+        assert_eq!(ignored("/repo/src/a/b/tmp", true), Some(true));
+        assert_eq!(ignored("/repo/#notes", false), Some(true));
+        assert_eq!(ignored("/other/debug.log", false), None);
+    }
+
+    #[test]
+    fn test_nested_files_take_precedence() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("repo/.git/info")).unwrap();
+        fs::create_dir_all(temp.path().join("repo/sub/deeper")).unwrap();
+        fs::write(temp.path().join("repo/.git/info/exclude"), "scratch/\n").unwrap();
+        fs::write(
+            temp.path().join("repo/.gitignore"),
+            "*.gen\n/sub/deeper/only\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join("repo/sub/.gitignore"), "!keep.gen\n").unwrap();
+        fs::write(
+            temp.path().join("repo/sub/.rootdetectignore"),
+            "fixtures/\n",
+        )
+        .unwrap();
+
+        // Start below the repository root: ancestor rules still apply
+        let start = temp.path().join("repo/sub");
+        let global = temp.path().join("global-ignore");
+        fs::write(&global, "*.swp\n").unwrap();
+        let stack =
+            IgnoreStack::for_start(&StdFs, &start, false, Some(&global)).enter(&StdFs, &start);
+
+        assert!(stack.is_ignored(&start.join("a.gen"), false));
+        assert!(!stack.is_ignored(&start.join("keep.gen"), false));
+        assert!(stack.is_ignored(&start.join("fixtures"), true));
+        assert!(stack.is_ignored(&start.join("scratch"), true));
+        assert!(stack.is_ignored(&start.join("deeper/only"), false));
+        assert!(stack.is_ignored(&start.join(".git"), true));
+        assert!(!stack.is_ignored(&start.join("main.rs"), false));
+        assert!(stack.is_ignored(&start.join("main.rs.swp"), false));
+
+        // Without a global excludes file only the tree's own files count
+        let local = IgnoreStack::for_start(&StdFs, &start, false, None).enter(&StdFs, &start);
+        assert!(!local.is_ignored(&start.join("main.rs.swp"), false));
+        assert!(local.is_ignored(&start.join("a.gen"), false));
+    }
+
+    #[test]
+    fn test_core_excludes_file() {
+        assert_eq!(
+            core_excludes_file("[user]\n\tname = x\n[core]\n\texcludesFile = \"~/.ignore\"\n"),
+            Some("~/.ignore".to_string())
+        );
+        assert_eq!(core_excludes_file("[user]\n\texcludesfile = x\n"), None);
+    }
+}
@@ -30,12 +30,50 @@
 //! // For batch processing with proper orphanage support, use find_roots_batch
 //! ```
 
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::hash::BuildHasher;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
+mod affected;
+mod archive;
+mod build_system;
+mod cache;
+mod config_file;
+mod filesystem;
+mod git_index;
+mod git_tree;
+mod glob;
+mod ignore;
+mod listing;
+mod marker;
+mod pattern;
+mod stream;
+mod walk;
+mod workspace;
+
+pub use affected::affected_roots;
+pub use archive::{load_archive, traverse_archive};
+pub use build_system::{BuildPackage, BUILD_FILES, BUILD_SYSTEM_ROOT_MARKERS};
+pub use cache::{cache_stats, clear_cache, default_cache_dir, CacheStats, MarkerCache};
+pub use config_file::{find_config_file, CONFIG_FILE_NAMES};
+pub use filesystem::{FileStamp, FileSystem, MemoryFs, StdFs};
+pub use git_index::git_tracked_files;
+pub use git_tree::{git_changed_files, git_merge_base};
+pub use ignore::{global_excludes_file, IGNORE_FILE_NAME};
+pub use listing::{find_root_infos_from_listing, find_roots_from_listing};
+pub use marker::{DecisionReason, Marker, MarkerDecision, MarkerPredicate, WEAK_DEFAULT_MARKERS};
+pub use pattern::PatternSet;
+pub use stream::{traverse_iter, TraversalIter};
+pub use workspace::{
+    find_package, find_workspace, load_workspace, PackageInfo, Workspace, WorkspaceInfo,
+    WorkspaceKind,
+};
+
 /// Default exclusion directory names (virtual envs, deps, build artifacts, caches)
 pub const DEFAULT_EXCLUSIONS: &[&str] = &[
     ".venv",
@@ -46,7 +84,7 @@ pub const DEFAULT_EXCLUSIONS: &[&str] = &[
     ".tox",
     "dist",
     "build",
-    ".egg-info",
+    "*.egg-info",
     ".mypy_cache",
     ".pytest_cache",
     ".ruff_cache",
@@ -55,23 +93,37 @@ pub const DEFAULT_EXCLUSIONS: &[&str] = &[
     ".gradle",
 ];
 
-/// Default project marker filenames
+/// Default project marker filenames (see [`WEAK_DEFAULT_MARKERS`] for their weights)
 pub const DEFAULT_MARKERS: &[&str] = &[
     ".git",
     ".hg",
     "pyproject.toml",
     "setup.py",
+    "setup.cfg",
     "package.json",
     "Cargo.toml",
     "go.mod",
+    "go.work",
     "pom.xml",
     "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
     "CMakeLists.txt",
     "deno.json",
     "composer.json",
     "mix.exs",
 ];
 
+/// .NET solution and project file globs, left out of [`DEFAULT_MARKERS`]
+/// because matching a glob lists every ancestor directory; added by
+/// [`Config::with_dotnet_markers`]
+pub const DOTNET_MARKERS: &[&str] = &["*.sln", "*.csproj", "*.fsproj"];
+
+/// .NET build settings files, which usually sit above the projects they
+/// configure; added as weak markers by [`Config::with_dotnet_markers`]
+pub const DOTNET_SETTINGS_MARKERS: &[&str] = &["Directory.Build.props", "global.json"];
+
 /// Errors that can occur during root detection
 #[derive(Error, Debug)]
 pub enum RootDetectionError {
@@ -82,90 +134,405 @@ pub enum RootDetectionError {
     /// The path has no parent directory (e.g., filesystem root)
     #[error("path has no parent directory")]
     NoParent,
+
+    /// A configuration file could not be read
+    #[error("failed to read config file {}: {source}", path.display())]
+    ConfigRead {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A root strategy name could not be parsed
+    #[error(
+        "invalid root strategy `{0}` (expected innermost, outermost, vcs, build_system or priority:<markers>)"
+    )]
+    InvalidStrategy(String),
+
+    /// A project kind name could not be parsed
+    #[error("invalid project kind `{0}`")]
+    InvalidProjectKind(String),
+
+    /// A configuration file could not be parsed
+    #[error("invalid config file {}: {message}", path.display())]
+    ConfigParse {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Parser error message
+        message: String,
+    },
+
+    /// A persistent cache file could not be written or removed
+    #[error("failed to write cache file {}: {source}", path.display())]
+    CacheWrite {
+        /// Path of the cache file
+        path: PathBuf,
+        /// Underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// An archive could not be read
+    #[error("failed to read archive {}: {source}", path.display())]
+    ArchiveRead {
+        /// Path of the archive
+        path: PathBuf,
+        /// Underlying I/O or format error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A git index could not be read
+    #[error("failed to read git index {}: {source}", path.display())]
+    GitIndexRead {
+        /// Path of the index, `.git` entry or starting directory
+        path: PathBuf,
+        /// Underlying I/O or format error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A git object or ref could not be read
+    #[error("failed to read git objects in {}: {source}", path.display())]
+    GitObjectRead {
+        /// Path of the git directory
+        path: PathBuf,
+        /// Underlying I/O or format error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A git revision did not name a commit, tag or tree
+    #[error("unknown git revision `{0}`")]
+    UnknownRevision(String),
+
+    /// Two git revisions share no history, so `A...B` has no merge base
+    #[error("git revisions `{0}` and `{1}` have no common ancestor")]
+    NoMergeBase(String, String),
+}
+
+/// Version control markers used by [`RootStrategy::Vcs`]
+pub const VCS_MARKERS: &[&str] = &[".git", ".hg", ".svn", ".jj", ".bzr", "_darcs", ".fslckout"];
+
+/// How to choose between several marker directories enclosing a file
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootStrategy {
+    /// Closest marker directory ("which package is this file in")
+    #[default]
+    Innermost,
+    /// Farthest marker directory below the first exclusion boundary
+    Outermost,
+    /// Closest directory containing a version control marker ("which repository")
+    Vcs,
+    /// Closest directory containing the first marker of the list that is found
+    /// anywhere in the ancestry, e.g. `["Cargo.toml", ".git"]`
+    Priority(Vec<String>),
+    /// Closest Bazel, Buck or Pants repository root, with the file's build
+    /// package in [`RootInfo::build_package`]; [`RootStrategy::Innermost`]
+    /// outside such repositories. `build` directories are not exclusion
+    /// boundaries with this strategy.
+    BuildSystem,
+}
+
+impl FromStr for RootStrategy {
+    type Err = RootDetectionError;
+
+    /// Parse `innermost`, `outermost`, `vcs`, `build_system` or
+    /// `priority:<marker>,<marker>,...`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "innermost" => Ok(Self::Innermost),
+            "outermost" => Ok(Self::Outermost),
+            "vcs" => Ok(Self::Vcs),
+            "build_system" => Ok(Self::BuildSystem),
+            _ => s
+                .strip_prefix("priority:")
+                .map(|list| {
+                    list.split(',')
+                        .map(str::trim)
+                        .filter(|m| !m.is_empty())
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|markers| !markers.is_empty())
+                .map(Self::Priority)
+                .ok_or_else(|| RootDetectionError::InvalidStrategy(s.to_string())),
+        }
+    }
 }
 
 /// Configuration for the root detection algorithm
 #[derive(Debug, Clone)]
 pub struct Config {
-    /// Directory names that mark exclusion zones
-    pub exclusions: HashSet<String>,
-    /// Filenames that mark project roots
-    pub markers: HashSet<String>,
+    /// Directory names, name globs and path globs that mark exclusion zones
+    pub exclusions: PatternSet,
+    /// Weighted filenames that mark project roots
+    pub markers: HashSet<Marker>,
     /// Whether to use case-insensitive matching (recommended for Windows/macOS)
     pub case_insensitive: bool,
+    /// Whether marker roots also report their enclosing workspace and package
+    /// metadata (see [`find_workspace`] and [`find_package`])
+    pub resolve_workspaces: bool,
+    /// Which marker directory to pick when several enclose a file
+    pub strategy: RootStrategy,
+    /// Filesystem that detection reads from ([`StdFs`] by default)
+    pub filesystem: Arc<dyn FileSystem>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            exclusions: DEFAULT_EXCLUSIONS
-                .iter()
-                .copied()
-                .map(String::from)
-                .collect(),
-            markers: DEFAULT_MARKERS.iter().copied().map(String::from).collect(),
+            exclusions: DEFAULT_EXCLUSIONS.iter().copied().collect(),
+            markers: DEFAULT_MARKERS.iter().copied().map(Marker::new).collect(),
             case_insensitive: cfg!(any(target_os = "windows", target_os = "macos")),
+            resolve_workspaces: false,
+            strategy: RootStrategy::Innermost,
+            filesystem: Arc::new(StdFs),
         }
     }
 }
 
 impl Config {
-    /// Create a new config with custom exclusions and markers
+    /// Create a new config with custom exclusions and markers (all of default weight)
     #[must_use]
     pub fn new(exclusions: &[&str], markers: &[&str]) -> Self {
         Self {
-            exclusions: exclusions.iter().copied().map(String::from).collect(),
-            markers: markers.iter().copied().map(String::from).collect(),
+            exclusions: exclusions.iter().copied().collect(),
+            markers: markers.iter().copied().map(Marker::from).collect(),
             case_insensitive: cfg!(any(target_os = "windows", target_os = "macos")),
+            resolve_workspaces: false,
+            strategy: RootStrategy::Innermost,
+            filesystem: Arc::new(StdFs),
         }
     }
 
     /// Add additional exclusion patterns
     #[must_use]
     pub fn with_exclusions(mut self, exclusions: &[&str]) -> Self {
-        self.exclusions
-            .extend(exclusions.iter().copied().map(String::from));
+        self.exclusions.extend(exclusions.iter().copied());
         self
     }
 
-    /// Add additional marker patterns
+    /// Add additional marker patterns of default weight
     #[must_use]
     pub fn with_markers(mut self, markers: &[&str]) -> Self {
         self.markers
-            .extend(markers.iter().copied().map(String::from));
+            .extend(markers.iter().copied().map(Marker::from));
         self
     }
 
-    fn matches_exclusion(&self, name: &str) -> bool {
-        if self.case_insensitive {
-            let lower = name.to_lowercase();
-            self.exclusions.iter().any(|e| e.to_lowercase() == lower)
-        } else {
-            self.exclusions.contains(name)
+    /// Add a marker, replacing the weight of an existing marker with the same name
+    #[must_use]
+    pub fn with_marker(mut self, marker: Marker) -> Self {
+        self.markers.replace(marker);
+        self
+    }
+
+    /// Give the configured [`WEAK_DEFAULT_MARKERS`] that still have the default
+    /// weight [`Marker::WEAK_WEIGHT`], so they defer to a heavier marker in an
+    /// enclosing directory
+    #[must_use]
+    pub fn with_weak_defaults(mut self) -> Self {
+        for name in WEAK_DEFAULT_MARKERS {
+            let weak = self
+                .markers
+                .get(*name)
+                .filter(|marker| marker.weight == Marker::DEFAULT_WEIGHT)
+                .map(|marker| marker.clone().with_weight(Marker::WEAK_WEIGHT));
+            if let Some(marker) = weak {
+                self.markers.replace(marker);
+            }
         }
+        self
     }
 
-    fn marker_exists_in(&self, dir: &Path) -> bool {
-        for marker in &self.markers {
-            let marker_path = dir.join(marker);
-            if marker_path.exists() {
-                return true;
+    /// Add the .NET markers: [`DOTNET_MARKERS`] at the default weight and
+    /// [`DOTNET_SETTINGS_MARKERS`] as weak markers. Markers already configured
+    /// keep their settings.
+    #[must_use]
+    pub fn with_dotnet_markers(mut self) -> Self {
+        let projects = DOTNET_MARKERS.iter().map(|name| Marker::new(*name));
+        let settings = DOTNET_SETTINGS_MARKERS
+            .iter()
+            .map(|name| Marker::weak(*name));
+        for marker in projects.chain(settings) {
+            if !self.markers.contains(marker.name()) {
+                self.markers.insert(marker);
             }
-            // Also check case-insensitive on Windows/macOS
-            if self.case_insensitive {
-                if let Ok(entries) = std::fs::read_dir(dir) {
-                    let lower_marker = marker.to_lowercase();
-                    for entry in entries.flatten() {
-                        if let Some(name) = entry.file_name().to_str() {
-                            if name.to_lowercase() == lower_marker {
-                                return true;
-                            }
-                        }
-                    }
-                }
+        }
+        self
+    }
+
+    /// Attach the built-in content predicates (see [`MarkerPredicate`]) to the
+    /// configured default markers that have no predicates of their own, so
+    /// that e.g. a `package.json` without `name` or `workspaces` is not a root
+    #[must_use]
+    pub fn with_builtin_predicates(mut self) -> Self {
+        self.markers = self
+            .markers
+            .into_iter()
+            .map(Marker::with_default_predicate)
+            .collect();
+        self
+    }
+
+    /// Enable or disable workspace and package metadata resolution
+    #[must_use]
+    pub const fn with_workspace_resolution(mut self, enabled: bool) -> Self {
+        self.resolve_workspaces = enabled;
+        self
+    }
+
+    /// Set the root resolution strategy
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: RootStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Read from `filesystem` instead of the real disk
+    #[must_use]
+    pub fn with_filesystem(mut self, filesystem: impl FileSystem + 'static) -> Self {
+        self.filesystem = Arc::new(filesystem);
+        self
+    }
+
+    /// This config with its path-glob exclusions anchored for a walk from
+    /// `root`: at `root` itself unless already anchored (at a config file's
+    /// directory), and under the spelling of `root` the walk uses
+    fn anchored_at(&self, root: &Path) -> Cow<'_, Self> {
+        if !self.exclusions.has_path_globs() {
+            return Cow::Borrowed(self);
+        }
+        let canonical = self
+            .filesystem
+            .canonicalize(root)
+            .unwrap_or_else(|_| root.to_path_buf());
+        let mut config = self.clone();
+        if !config.exclusions.is_anchored() {
+            config.exclusions.anchor_at(&canonical);
+        }
+        config.exclusions.add_alias(root, &canonical);
+        Cow::Owned(config)
+    }
+
+    /// Whether `dir` is an exclusion boundary
+    fn is_exclusion_dir(&self, dir: &Path) -> bool {
+        self.exclusions.matches_dir(dir, self.case_insensitive)
+            && !dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.is_build_package_name(name))
+    }
+
+    /// Whether `name` is an ordinary package directory despite matching an
+    /// exclusion: Bazel, Buck and Pants keep their outputs in `bazel-*`,
+    /// `buck-out` and `dist`, so with [`RootStrategy::BuildSystem`] a `build`
+    /// directory holds sources
+    fn is_build_package_name(&self, name: &str) -> bool {
+        self.strategy == RootStrategy::BuildSystem
+            && if self.case_insensitive {
+                name.eq_ignore_ascii_case("build")
+            } else {
+                name == "build"
             }
+    }
+
+    /// Markers from this config present in `dir`, sorted by name
+    fn markers_in(&self, dir: &Path, cache: Option<&MarkerCache>) -> Vec<&Marker> {
+        if let Some(names) = cache.and_then(|c| c.get(&*self.filesystem, dir)) {
+            return names
+                .iter()
+                .filter_map(|name| self.markers.get(name.as_str()))
+                .collect();
+        }
+
+        let stamp = cache.and_then(|c| c.stamp(&*self.filesystem, dir));
+        let found = self.present_markers(dir, &self.markers);
+        if let Some(c) = cache {
+            c.insert(
+                dir,
+                found.iter().map(|m| m.name().to_string()).collect(),
+                stamp,
+            );
+        }
+        found
+    }
+
+    /// Which of `candidates` are present in `dir`, sorted by name. Configured
+    /// markers are answered from `cache`, others are checked on disk.
+    fn candidates_in<'m>(
+        &self,
+        dir: &Path,
+        candidates: &'m [Marker],
+        cache: Option<&MarkerCache>,
+    ) -> Vec<&'m Marker> {
+        if cache.is_none() {
+            return self.present_markers(dir, candidates);
         }
-        false
+
+        let (configured, unconfigured): (Vec<&Marker>, Vec<&Marker>) = candidates
+            .iter()
+            .partition(|m| self.markers.contains(m.name()));
+        let cached: Vec<&str> = self
+            .markers_in(dir, cache)
+            .into_iter()
+            .map(Marker::name)
+            .collect();
+
+        let mut found: Vec<&Marker> = configured
+            .into_iter()
+            .filter(|m| cached.contains(&m.name()))
+            .collect();
+        found.extend(self.present_markers(dir, unconfigured));
+        found.sort_by(|a, b| a.name().cmp(b.name()));
+        found
+    }
+
+    /// The configured marker called `name`, or a default-weight one if not configured
+    fn marker_named(&self, name: &str) -> Marker {
+        self.markers
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| Marker::new(name))
+    }
+
+    /// Which of `candidates` exist in `dir` and meet their content predicates,
+    /// sorted by name
+    fn present_markers<'m>(
+        &self,
+        dir: &Path,
+        candidates: impl IntoIterator<Item = &'m Marker>,
+    ) -> Vec<&'m Marker> {
+        let candidates: Vec<&Marker> = candidates.into_iter().collect();
+
+        // Globs and case-insensitive lookups read the directory once, not once per marker
+        let entries: Vec<String> =
+            if self.case_insensitive || candidates.iter().any(|m| m.is_glob()) {
+                self.filesystem
+                    .read_dir(dir)
+                    .map(|entries| {
+                        entries
+                            .iter()
+                            .filter_map(|e| e.file_name()?.to_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+
+        let mut found: Vec<&Marker> = candidates
+            .into_iter()
+            .filter(|marker| {
+                marker.is_present_in(&*self.filesystem, dir, &entries, self.case_insensitive)
+            })
+            .collect();
+        found.sort_by(|a, b| a.name().cmp(b.name()));
+        found
     }
 }
 
@@ -207,7 +574,7 @@ impl ExclusionCache {
 #[must_use]
 pub fn is_excluded(path: &Path, config: &Config, cache: Option<&ExclusionCache>) -> bool {
     // Try to resolve symlinks
-    let Ok(resolved) = path.canonicalize() else {
+    let Ok(resolved) = config.filesystem.canonicalize(path) else {
         return true; // Treat unresolvable paths as excluded
     };
 
@@ -218,11 +585,14 @@ pub fn is_excluded(path: &Path, config: &Config, cache: Option<&ExclusionCache>)
         }
     }
 
-    // Check if any path component is an exclusion boundary
-    let excluded = resolved
-        .components()
-        .filter_map(|c| c.as_os_str().to_str())
-        .any(|name| config.matches_exclusion(name));
+    // Check if any ancestor directory is an exclusion boundary
+    let components = pattern::path_components(&resolved);
+    let excluded = (1..=components.len()).any(|end| {
+        config
+            .exclusions
+            .matches_components(&components[..end], config.case_insensitive)
+            && !config.is_build_package_name(components[end - 1])
+    });
 
     // Update cache
     if let Some(c) = cache {
@@ -232,31 +602,159 @@ pub fn is_excluded(path: &Path, config: &Config, cache: Option<&ExclusionCache>)
     excluded
 }
 
-/// Find the innermost marker directory for a source file
-fn find_marker_root(source: &Path, config: &Config) -> Option<PathBuf> {
-    let mut current = source.parent()?;
+/// Ancestors of `dir` (inclusive), stopping before the first exclusion boundary
+fn ancestors_within<'a>(dir: &'a Path, config: &'a Config) -> impl Iterator<Item = &'a Path> {
+    dir.ancestors()
+        .take_while(|ancestor| !config.is_exclusion_dir(ancestor))
+}
+
+/// Marker directory chosen for a source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MarkerRoot {
+    root: PathBuf,
+    markers: Vec<String>,
+    decision: MarkerDecision,
+    build_package: Option<BuildPackage>,
+}
 
-    loop {
-        // Check if this directory is an exclusion boundary (stop searching)
-        if let Some(name) = current.file_name().and_then(|n| n.to_str()) {
-            if config.matches_exclusion(name) {
-                break;
+impl MarkerRoot {
+    fn new(dir: &Path, found: &[&Marker], reason: DecisionReason) -> Option<Self> {
+        Some(Self {
+            root: dir.to_path_buf(),
+            markers: found.iter().map(|m| m.name().to_string()).collect(),
+            decision: MarkerDecision::heaviest(found, reason)?,
+            build_package: None,
+        })
+    }
+}
+
+/// Find the marker directory for a source file according to [`Config::strategy`],
+/// reusing the root that `cache` holds for the file's directory
+fn find_marker_root(
+    source: &Path,
+    config: &Config,
+    cache: Option<&MarkerCache>,
+) -> Option<MarkerRoot> {
+    let dir = source.parent()?;
+    let Some(cache) = cache else {
+        return decide_marker_root(source, config, None);
+    };
+    if let Some(root) = cache.root(&*config.filesystem, dir) {
+        return root;
+    }
+
+    // Stamped before reading, so a concurrent change invalidates the entry
+    let stamps = cache.stamps(&*config.filesystem, ancestors_within(dir, config));
+    let root = decide_marker_root(source, config, Some(cache));
+    cache.insert_root(dir, root.as_ref(), stamps);
+    root
+}
+
+/// Marker directories enclosing `dir` (inclusive), innermost first, with
+/// the markers each holds
+fn marker_dirs<'a>(
+    dir: &'a Path,
+    config: &'a Config,
+    cache: Option<&'a MarkerCache>,
+) -> impl Iterator<Item = (&'a Path, Vec<&'a Marker>)> {
+    ancestors_within(dir, config)
+        .map(move |dir| (dir, config.markers_in(dir, cache)))
+        .filter(|(_, found)| !found.is_empty())
+}
+
+/// Closest marker directory enclosing `dir`, unless an enclosing one has a
+/// strictly heavier marker
+fn innermost_root(dir: &Path, config: &Config, cache: Option<&MarkerCache>) -> Option<MarkerRoot> {
+    let heaviest = |found: &[&Marker]| found.iter().map(|m| m.weight).max();
+    let max_weight = config.markers.iter().map(|m| m.weight).max();
+    let mut dirs = marker_dirs(dir, config, cache);
+    let (closest, closest_found) = dirs.next()?;
+    let mut best = (closest, heaviest(&closest_found), closest_found);
+    if best.1 != max_weight {
+        for (dir, found) in dirs {
+            let weight = heaviest(&found);
+            if weight > best.1 {
+                best = (dir, weight, found);
+                if weight == max_weight {
+                    break;
+                }
             }
         }
+    }
+
+    let (dir, _, found) = best;
+    if dir == closest {
+        MarkerRoot::new(dir, &found, DecisionReason::Innermost)
+    } else {
+        let mut root = MarkerRoot::new(dir, &found, DecisionReason::Outweighed)?;
+        root.decision.displaced = Some(closest.to_path_buf());
+        Some(root)
+    }
+}
 
-        // Check for any project marker in this directory
-        if config.marker_exists_in(current) {
-            return Some(current.to_path_buf());
+fn decide_marker_root(
+    source: &Path,
+    config: &Config,
+    cache: Option<&MarkerCache>,
+) -> Option<MarkerRoot> {
+    match &config.strategy {
+        RootStrategy::Innermost => innermost_root(source.parent()?, config, cache),
+        RootStrategy::Outermost => marker_dirs(source.parent()?, config, cache)
+            .last()
+            .and_then(|(dir, found)| MarkerRoot::new(dir, &found, DecisionReason::Outermost)),
+        RootStrategy::Vcs => {
+            let vcs: Vec<Marker> = VCS_MARKERS.iter().map(|n| config.marker_named(n)).collect();
+            ancestors_within(source.parent()?, config).find_map(|dir| {
+                let found = config.candidates_in(dir, &vcs, cache);
+                MarkerRoot::new(dir, &found, DecisionReason::Vcs)
+            })
+        }
+        RootStrategy::BuildSystem => {
+            let roots: Vec<Marker> = BUILD_SYSTEM_ROOT_MARKERS
+                .iter()
+                .map(|n| config.marker_named(n))
+                .collect();
+            let build_root = ancestors_within(source.parent()?, config).find_map(|dir| {
+                let found = config.candidates_in(dir, &roots, cache);
+                MarkerRoot::new(dir, &found, DecisionReason::BuildSystem)
+            });
+            // Outside Bazel, Buck and Pants repositories, roots are found as usual
+            let Some(mut root) = build_root else {
+                return innermost_root(source.parent()?, config, cache);
+            };
+            root.build_package =
+                build_system::find_build_package(source, &root.root, &*config.filesystem);
+            Some(root)
         }
+        RootStrategy::Priority(priority) => {
+            let ranked: Vec<Marker> = priority.iter().map(|n| config.marker_named(n)).collect();
+
+            // Innermost directory for the best-ranked marker seen so far
+            let mut best: Option<(usize, &Path)> = None;
+            for dir in ancestors_within(source.parent()?, config) {
+                let present = config.candidates_in(dir, &ranked, cache);
+                let rank = ranked.iter().position(|m| present.contains(&m));
+                if let Some(rank) = rank {
+                    if best.map_or(true, |(best_rank, _)| rank < best_rank) {
+                        best = Some((rank, dir));
+                    }
+                    if rank == 0 {
+                        break;
+                    }
+                }
+            }
 
-        // Move to parent
-        match current.parent() {
-            Some(parent) if parent != current => current = parent,
-            _ => break,
+            let (rank, dir) = best?;
+            let mut root = MarkerRoot::new(
+                dir,
+                &config.candidates_in(dir, &ranked, cache),
+                DecisionReason::Priority,
+            )?;
+            root.decision.marker = ranked[rank].name().to_string();
+            root.decision.weight = ranked[rank].weight;
+            Some(root)
         }
     }
-
-    None
 }
 
 /// Find the "orphanage" for an orphan file (no marker found).
@@ -309,11 +807,16 @@ fn find_marker_root(source: &Path, config: &Config) -> Option<PathBuf> {
 ///
 /// * `source` - The orphan source file
 /// * `source_dirs` - Pre-computed set of directories containing valid source files
+/// * `fs` - Filesystem to list sibling directories from
 ///
 /// # Returns
 ///
 /// The appropriate orphanage directory for this file.
-fn find_orphanage<S: BuildHasher>(source: &Path, source_dirs: &HashSet<PathBuf, S>) -> PathBuf {
+fn find_orphanage<S: BuildHasher>(
+    source: &Path,
+    source_dirs: &HashSet<PathBuf, S>,
+    fs: &dyn FileSystem,
+) -> PathBuf {
     let mut candidate = source.parent().unwrap_or(source).to_path_buf();
 
     loop {
@@ -330,7 +833,7 @@ fn find_orphanage<S: BuildHasher>(source: &Path, source_dirs: &HashSet<PathBuf,
         // Check if we should go up:
         // 1. Parent has sibling directories with sources, OR
         // 2. Parent (or any ancestor of parent) is a SourceDir
-        let has_sibling_sources = has_sibling_source_dirs(&candidate, parent, source_dirs);
+        let has_sibling_sources = has_sibling_source_dirs(&candidate, parent, source_dirs, fs);
         let parent_or_ancestor_is_source_dir = is_or_has_ancestor_source_dir(parent, source_dirs);
 
         if has_sibling_sources || parent_or_ancestor_is_source_dir {
@@ -365,17 +868,16 @@ fn has_sibling_source_dirs<S: BuildHasher>(
     current: &Path,
     parent: &Path,
     source_dirs: &HashSet<PathBuf, S>,
+    fs: &dyn FileSystem,
 ) -> bool {
     // Read parent directory to find siblings
-    let Ok(entries) = std::fs::read_dir(parent) else {
+    let Ok(entries) = fs.read_dir(parent) else {
         return false;
     };
 
-    for entry in entries.flatten() {
-        let sibling = entry.path();
-
+    for sibling in entries {
         // Skip if it's not a directory or if it's the current path
-        if !sibling.is_dir() || sibling == current {
+        if !fs.is_dir(&sibling) || sibling == current {
             continue;
         }
 
@@ -396,11 +898,14 @@ fn has_sibling_source_dirs<S: BuildHasher>(
 }
 
 /// Compute the Lowest Common Ancestor of a set of paths
-fn compute_lca<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+fn compute_lca<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    fs: &dyn FileSystem,
+) -> Option<PathBuf> {
     let mut common_ancestors: Option<HashSet<PathBuf>> = None;
 
     for path in paths {
-        let Ok(resolved) = path.canonicalize() else {
+        let Ok(resolved) = fs.canonicalize(path) else {
             continue;
         };
 
@@ -428,6 +933,171 @@ fn compute_lca<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf>
         .max_by_key(|p| p.components().count())
 }
 
+/// Which case of the algorithm produced a root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionCase {
+    /// Case 2: innermost directory containing a project marker
+    Marker,
+    /// Case 3: lowest common ancestor of the dependency cluster
+    DependencyCluster,
+    /// Case 4: orphanage, or the parent directory without `SourceDirs`
+    Orphanage,
+}
+
+impl DetectionCase {
+    /// Case number as listed in the algorithm (case 1, exclusion, yields no root)
+    #[must_use]
+    pub const fn number(self) -> u8 {
+        match self {
+            Self::Marker => 2,
+            Self::DependencyCluster => 3,
+            Self::Orphanage => 4,
+        }
+    }
+}
+
+/// Kind of project, inferred from the markers found at its root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    /// Cargo package or workspace
+    Rust,
+    /// npm / yarn / pnpm package
+    Node,
+    /// Deno project
+    Deno,
+    /// Python project (`pyproject.toml`, `setup.py`, `setup.cfg`)
+    Python,
+    /// Go module or `go.work` workspace
+    Go,
+    /// Maven or Gradle build
+    Jvm,
+    /// CMake project
+    Cmake,
+    /// Composer package
+    Php,
+    /// Mix project
+    Elixir,
+    /// .NET project or solution (`*.csproj`, `*.fsproj`, `*.sln`)
+    Dotnet,
+    /// No ecosystem-specific marker (VCS root, orphanage, custom marker)
+    Unknown,
+}
+
+/// Ecosystem markers in order of precedence when a root has several
+const KIND_MARKERS: &[(&str, ProjectKind)] = &[
+    ("Cargo.toml", ProjectKind::Rust),
+    ("go.mod", ProjectKind::Go),
+    ("go.work", ProjectKind::Go),
+    ("pom.xml", ProjectKind::Jvm),
+    ("build.gradle", ProjectKind::Jvm),
+    ("build.gradle.kts", ProjectKind::Jvm),
+    ("settings.gradle", ProjectKind::Jvm),
+    ("settings.gradle.kts", ProjectKind::Jvm),
+    ("*.csproj", ProjectKind::Dotnet),
+    ("*.fsproj", ProjectKind::Dotnet),
+    ("*.sln", ProjectKind::Dotnet),
+    ("pyproject.toml", ProjectKind::Python),
+    ("setup.py", ProjectKind::Python),
+    ("setup.cfg", ProjectKind::Python),
+    ("package.json", ProjectKind::Node),
+    ("deno.json", ProjectKind::Deno),
+    ("composer.json", ProjectKind::Php),
+    ("mix.exs", ProjectKind::Elixir),
+    ("CMakeLists.txt", ProjectKind::Cmake),
+];
+
+impl ProjectKind {
+    /// Infer the project kind from the marker names found at a root
+    #[must_use]
+    pub fn from_markers<S: AsRef<str>>(markers: &[S]) -> Self {
+        KIND_MARKERS
+            .iter()
+            .find(|(name, _)| {
+                markers
+                    .iter()
+                    .any(|m| m.as_ref().eq_ignore_ascii_case(name))
+            })
+            .map_or(Self::Unknown, |(_, kind)| *kind)
+    }
+}
+
+impl FromStr for ProjectKind {
+    type Err = RootDetectionError;
+
+    /// Parse a kind by its lowercase name, as serialized (`rust`, `node`, ...)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rust" => Ok(Self::Rust),
+            "node" => Ok(Self::Node),
+            "deno" => Ok(Self::Deno),
+            "python" => Ok(Self::Python),
+            "go" => Ok(Self::Go),
+            "jvm" => Ok(Self::Jvm),
+            "cmake" => Ok(Self::Cmake),
+            "php" => Ok(Self::Php),
+            "elixir" => Ok(Self::Elixir),
+            "dotnet" => Ok(Self::Dotnet),
+            "unknown" => Ok(Self::Unknown),
+            _ => Err(RootDetectionError::InvalidProjectKind(s.to_string())),
+        }
+    }
+}
+
+/// A detected project root with details on how it was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RootInfo {
+    /// The project root directory
+    pub root: PathBuf,
+    /// Markers found in the root directory, sorted by name (empty for cases 3 and 4)
+    pub markers: Vec<String>,
+    /// Which case of the algorithm produced the root
+    pub case: DetectionCase,
+    /// Project kind inferred from the markers
+    pub kind: ProjectKind,
+    /// Which marker decided the root and why (case 2 only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision: Option<MarkerDecision>,
+    /// Enclosing workspace, when [`Config::resolve_workspaces`] is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceInfo>,
+    /// Package name and import directory, when [`Config::resolve_workspaces`] is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<PackageInfo>,
+    /// Build package of the file, with [`RootStrategy::BuildSystem`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_package: Option<BuildPackage>,
+}
+
+impl RootInfo {
+    fn new(root: PathBuf, markers: Vec<String>, case: DetectionCase) -> Self {
+        let kind = ProjectKind::from_markers(&markers);
+        Self {
+            root,
+            markers,
+            case,
+            kind,
+            decision: None,
+            workspace: None,
+            package: None,
+            build_package: None,
+        }
+    }
+
+    /// Case 2 root, with its workspace and package when [`Config::resolve_workspaces`] is enabled
+    fn marker(found: MarkerRoot, config: &Config) -> Self {
+        let mut info = Self::new(found.root, found.markers, DetectionCase::Marker);
+        info.decision = Some(found.decision);
+        info.build_package = found.build_package;
+        if config.resolve_workspaces {
+            info.workspace = find_workspace(&info.root, config);
+            info.package = find_package(&info.root, config);
+        }
+        info
+    }
+}
+
 /// Find the project root for a source file.
 ///
 /// # Arguments
@@ -470,14 +1140,63 @@ pub fn find_root_with_cache<S1: BuildHasher, S2: BuildHasher>(
     config: &Config,
     cache: Option<&ExclusionCache>,
 ) -> Option<PathBuf> {
+    find_root_info_with_cache(source_file, source_dirs, dependency_cluster, config, cache)
+        .map(|info| info.root)
+}
+
+/// Find the project root for a source file, reporting how it was detected.
+///
+/// Same algorithm and arguments as [`find_root`], but returns a [`RootInfo`]
+/// with the matched markers, the detection case and the inferred project kind.
+#[must_use]
+pub fn find_root_info<S1: BuildHasher, S2: BuildHasher>(
+    source_file: &Path,
+    source_dirs: Option<&HashSet<PathBuf, S1>>,
+    dependency_cluster: Option<&HashSet<PathBuf, S2>>,
+    config: &Config,
+) -> Option<RootInfo> {
+    find_root_info_with_cache(source_file, source_dirs, dependency_cluster, config, None)
+}
+
+/// Find the project root with detection details and an optional exclusion cache.
+#[must_use]
+pub fn find_root_info_with_cache<S1: BuildHasher, S2: BuildHasher>(
+    source_file: &Path,
+    source_dirs: Option<&HashSet<PathBuf, S1>>,
+    dependency_cluster: Option<&HashSet<PathBuf, S2>>,
+    config: &Config,
+    cache: Option<&ExclusionCache>,
+) -> Option<RootInfo> {
+    find_root_info_with_caches(
+        source_file,
+        source_dirs,
+        dependency_cluster,
+        config,
+        cache,
+        None,
+    )
+}
+
+/// Find the project root with detection details and optional exclusion and
+/// marker caches. Files in the same directory share one marker lookup per
+/// ancestor when they share a [`MarkerCache`].
+#[must_use]
+pub fn find_root_info_with_caches<S1: BuildHasher, S2: BuildHasher>(
+    source_file: &Path,
+    source_dirs: Option<&HashSet<PathBuf, S1>>,
+    dependency_cluster: Option<&HashSet<PathBuf, S2>>,
+    config: &Config,
+    cache: Option<&ExclusionCache>,
+    marker_cache: Option<&MarkerCache>,
+) -> Option<RootInfo> {
     // Case 1: Check if file is excluded
     if is_excluded(source_file, config, cache) {
         return None;
     }
 
     // Case 2: Search for marker directories (innermost first)
-    if let Some(root) = find_marker_root(source_file, config) {
-        return Some(root);
+    if let Some(found) = find_marker_root(source_file, config, marker_cache) {
+        return Some(RootInfo::marker(found, config));
     }
 
     // Case 3: Orphan with dependency cluster
@@ -489,20 +1208,54 @@ pub fn find_root_with_cache<S1: BuildHasher, S2: BuildHasher>(
             .collect();
 
         if valid_files.len() > 1 {
-            if let Some(lca) = compute_lca(valid_files) {
-                return Some(lca);
+            if let Some(lca) = compute_lca(valid_files, &*config.filesystem) {
+                return Some(RootInfo::new(
+                    lca,
+                    Vec::new(),
+                    DetectionCase::DependencyCluster,
+                ));
             }
         }
     }
 
     // Case 4: Orphan - find the orphanage (outermost SourceDir in ancestry)
-    source_dirs.map_or_else(
-        || Some(source_file.parent().unwrap_or(source_file).to_path_buf()),
-        |dirs| Some(find_orphanage(source_file, dirs)),
-    )
+    let root = source_dirs.map_or_else(
+        || source_file.parent().unwrap_or(source_file).to_path_buf(),
+        |dirs| find_orphanage(source_file, dirs, &*config.filesystem),
+    );
+    Some(RootInfo::new(root, Vec::new(), DetectionCase::Orphanage))
+}
+
+/// Find every marker root enclosing a source file, ordered innermost to outermost.
+///
+/// For `mono/packages/api/src/index.ts` this returns `mono/packages/api`
+/// (via `package.json`) followed by `mono` (via `.git`), so a file can be
+/// attributed to both its package and its repository. The search stops at the
+/// first exclusion boundary and ignores [`Config::strategy`].
+///
+/// Returns an empty vector if the file is excluded or no marker encloses it.
+#[must_use]
+pub fn find_root_chain(source_file: &Path, config: &Config) -> Vec<RootInfo> {
+    if is_excluded(source_file, config, None) {
+        return Vec::new();
+    }
+    let Some(parent) = source_file.parent() else {
+        return Vec::new();
+    };
+
+    ancestors_within(parent, config)
+        .filter_map(|dir| {
+            let found = MarkerRoot::new(
+                dir,
+                &config.markers_in(dir, None),
+                DecisionReason::Enclosing,
+            )?;
+            Some(RootInfo::marker(found, config))
+        })
+        .collect()
 }
 
-/// Batch process multiple source files efficiently using a shared cache.
+/// Batch process multiple source files efficiently using shared caches.
 ///
 /// This is the recommended API for processing multiple files, as it computes
 /// `SourceDirs` upfront to enable correct orphanage detection.
@@ -511,6 +1264,29 @@ pub fn find_roots_batch<'a>(
     source_files: impl IntoIterator<Item = &'a Path>,
     config: &Config,
 ) -> Vec<(&'a Path, Option<PathBuf>)> {
+    find_root_infos_batch(source_files, config)
+        .into_iter()
+        .map(|(path, info)| (path, info.map(|i| i.root)))
+        .collect()
+}
+
+/// Batch variant of [`find_root_info`], see [`find_roots_batch`].
+#[must_use]
+pub fn find_root_infos_batch<'a>(
+    source_files: impl IntoIterator<Item = &'a Path>,
+    config: &Config,
+) -> Vec<(&'a Path, Option<RootInfo>)> {
+    find_root_infos_batch_with_cache(source_files, config, &MarkerCache::new())
+}
+
+/// [`find_root_infos_batch`] with a caller-provided [`MarkerCache`], such as
+/// a persistent one from [`MarkerCache::open`].
+#[must_use]
+pub fn find_root_infos_batch_with_cache<'a>(
+    source_files: impl IntoIterator<Item = &'a Path>,
+    config: &Config,
+    marker_cache: &MarkerCache,
+) -> Vec<(&'a Path, Option<RootInfo>)> {
     let cache = ExclusionCache::new();
     let files: Vec<&'a Path> = source_files.into_iter().collect();
 
@@ -526,10 +1302,17 @@ pub fn find_roots_batch<'a>(
         .map(|path| {
             (
                 path,
-                find_root_with_cache::<
+                find_root_info_with_caches::<
                     std::collections::hash_map::RandomState,
                     std::collections::hash_map::RandomState,
-                >(path, Some(&source_dirs), None, config, Some(&cache)),
+                >(
+                    path,
+                    Some(&source_dirs),
+                    None,
+                    config,
+                    Some(&cache),
+                    Some(marker_cache),
+                ),
             )
         })
         .collect()
@@ -542,6 +1325,8 @@ pub struct TraversalResult {
     pub file: PathBuf,
     /// The detected project root (None if excluded)
     pub root: Option<PathBuf>,
+    /// Detection details for the root (None if excluded)
+    pub info: Option<RootInfo>,
 }
 
 /// Options for filesystem traversal
@@ -552,6 +1337,14 @@ pub struct TraversalOptions {
     pub extensions: HashSet<String>,
     /// Maximum directory depth to traverse (None for unlimited)
     pub max_depth: Option<usize>,
+    /// Skip paths ignored by `.gitignore`, `.git/info/exclude`,
+    /// `global_excludes_file` and [`IGNORE_FILE_NAME`] files
+    pub respect_ignore_files: bool,
+    /// Excludes file read before the tree's ignore files, such as git's
+    /// [`global_excludes_file`]; `None` reads only files inside the tree
+    pub global_excludes_file: Option<PathBuf>,
+    /// Worker threads for walking and root detection (0 = available parallelism)
+    pub threads: usize,
 }
 
 impl TraversalOptions {
@@ -569,6 +1362,35 @@ impl TraversalOptions {
         self
     }
 
+    /// Enable or disable ignore file handling
+    #[must_use]
+    pub const fn with_ignore_files(mut self, enabled: bool) -> Self {
+        self.respect_ignore_files = enabled;
+        self
+    }
+
+    /// Set the excludes file read before the tree's ignore files
+    #[must_use]
+    pub fn with_global_excludes_file(mut self, path: Option<PathBuf>) -> Self {
+        self.global_excludes_file = path;
+        self
+    }
+
+    /// Set the number of worker threads (0 = available parallelism)
+    #[must_use]
+    pub const fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    fn thread_count(&self) -> usize {
+        if self.threads == 0 {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        } else {
+            self.threads
+        }
+    }
+
     fn matches_extension(&self, path: &Path) -> bool {
         if self.extensions.is_empty() {
             return true;
@@ -590,6 +1412,10 @@ impl TraversalOptions {
 /// 2. Then, compute `SourceDirs` (directories containing source files)
 /// 3. Finally, detect roots with correct orphanage behavior
 ///
+/// Each phase runs on [`TraversalOptions::threads`] threads sharing one
+/// [`ExclusionCache`] and one [`MarkerCache`]; results are sorted by file
+/// path either way.
+///
 /// # Arguments
 ///
 /// * `start_path` - Directory to start traversal from
@@ -605,71 +1431,48 @@ pub fn traverse_and_detect(
     config: &Config,
     options: &TraversalOptions,
 ) -> Vec<TraversalResult> {
+    let config = &*config.anchored_at(start_path);
     let cache = ExclusionCache::new();
+    let marker_cache = MarkerCache::new();
+    let threads = options.thread_count();
 
-    // Phase 1: Collect all source files
-    let mut files = Vec::new();
-    collect_files_recursive(start_path, config, options, 0, &mut files);
+    // Phase 1: Collect all source files (sorted, so output order is stable)
+    let files = walk::collect_files(start_path, config, options);
 
     // Phase 2: Compute SourceDirs (directories containing valid source files)
+    let excluded = walk::parallel_map(&files, threads, |f| is_excluded(f, config, Some(&cache)));
     let source_dirs: HashSet<PathBuf> = files
         .iter()
-        .filter(|f| !is_excluded(f, config, Some(&cache)))
-        .filter_map(|f| f.parent().map(Path::to_path_buf))
+        .zip(&excluded)
+        .filter(|(_, excluded)| !**excluded)
+        .filter_map(|(f, _)| f.parent().map(Path::to_path_buf))
         .collect();
 
     // Phase 3: Detect roots with proper orphanage support
+    let infos = walk::parallel_map(&files, threads, |file| {
+        find_root_info_with_caches::<
+            std::collections::hash_map::RandomState,
+            std::collections::hash_map::RandomState,
+        >(
+            file,
+            Some(&source_dirs),
+            None,
+            config,
+            Some(&cache),
+            Some(&marker_cache),
+        )
+    });
     files
         .into_iter()
-        .map(|file| {
-            let root = find_root_with_cache::<
-                std::collections::hash_map::RandomState,
-                std::collections::hash_map::RandomState,
-            >(&file, Some(&source_dirs), None, config, Some(&cache));
-            TraversalResult { file, root }
+        .zip(infos)
+        .map(|(file, info)| TraversalResult {
+            file,
+            root: info.as_ref().map(|i| i.root.clone()),
+            info,
         })
         .collect()
 }
 
-fn collect_files_recursive(
-    dir: &Path,
-    config: &Config,
-    options: &TraversalOptions,
-    depth: usize,
-    files: &mut Vec<PathBuf>,
-) {
-    // Check max depth
-    if let Some(max) = options.max_depth {
-        if depth > max {
-            return;
-        }
-    }
-
-    // Check if this directory is an exclusion boundary
-    if let Some(name) = dir.file_name().and_then(|n| n.to_str()) {
-        if config.matches_exclusion(name) {
-            return; // Don't descend into exclusion zones
-        }
-    }
-
-    // Read directory entries
-    let Ok(entries) = std::fs::read_dir(dir) else {
-        return; // Skip unreadable directories
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-
-        if path.is_dir() {
-            // Recurse into subdirectory
-            collect_files_recursive(&path, config, options, depth + 1, files);
-        } else if path.is_file() && options.matches_extension(&path) {
-            // Found a source file
-            files.push(path);
-        }
-    }
-}
-
 /// Traverse and return only the unique project roots discovered
 #[must_use]
 pub fn discover_roots(
@@ -1091,6 +1894,427 @@ mod tests {
         cache.clear();
     }
 
+    #[test]
+    fn test_marker_cache() {
+        let temp = setup_project(&[
+            ("repo/.git", true),
+            ("repo/pkg/package.json", false),
+            ("repo/pkg/src/a.ts", false),
+            ("repo/pkg/src/b.ts", false),
+        ]);
+        let config = Config::default();
+        let cache = MarkerCache::new();
+        let lookup = |path: &str, config: &Config| {
+            find_root_info_with_caches(
+                &temp.path().join(path),
+                None::<&StdHashSet>,
+                None::<&StdHashSet>,
+                config,
+                None,
+                Some(&cache),
+            )
+            .unwrap()
+        };
+
+        // Innermost stops at pkg/: src/ and pkg/ are cached
+        assert_eq!(
+            lookup("repo/pkg/src/a.ts", &config).root,
+            temp.path().join("repo/pkg")
+        );
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            lookup("repo/pkg/src/b.ts", &config).root,
+            temp.path().join("repo/pkg")
+        );
+        assert_eq!(cache.len(), 2);
+
+        // Answers come from the cache until it is cleared
+        fs::remove_file(temp.path().join("repo/pkg/package.json")).unwrap();
+        assert_eq!(
+            lookup("repo/pkg/src/a.ts", &config).root,
+            temp.path().join("repo/pkg")
+        );
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(
+            lookup("repo/pkg/src/a.ts", &config).root,
+            temp.path().join("repo")
+        );
+
+        // Strategies with their own candidates reuse the cached directories
+        let vcs = Config::default().with_strategy(RootStrategy::Vcs);
+        let info = lookup("repo/pkg/src/b.ts", &vcs);
+        assert_eq!(info.root, temp.path().join("repo"));
+        assert_eq!(info.markers, vec![".git".to_string()]);
+    }
+
+    #[test]
+    fn test_root_info_reports_markers_and_kind() {
+        let temp = setup_project(&[
+            (".git", true),
+            ("Cargo.toml", false),
+            ("src/main.rs", false),
+        ]);
+
+        let config = Config::default();
+        let source = temp.path().join("src/main.rs");
+
+        let info =
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap();
+        assert_eq!(info.root, temp.path().to_path_buf());
+        assert_eq!(
+            info.markers,
+            vec![".git".to_string(), "Cargo.toml".to_string()]
+        );
+        assert_eq!(info.case, DetectionCase::Marker);
+        assert_eq!(info.case.number(), 2);
+        assert_eq!(info.kind, ProjectKind::Rust);
+    }
+
+    #[test]
+    fn test_root_info_orphan_and_cluster_cases() {
+        let temp = setup_project(&[("scripts/a.py", false), ("scripts/utils/b.py", false)]);
+
+        let config = Config::default();
+        let source = temp.path().join("scripts/a.py");
+
+        let info =
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap();
+        assert_eq!(info.case, DetectionCase::Orphanage);
+        assert!(info.markers.is_empty());
+        assert_eq!(info.kind, ProjectKind::Unknown);
+
+        let cluster: HashSet<PathBuf> = [
+            temp.path().join("scripts/a.py"),
+            temp.path().join("scripts/utils/b.py"),
+        ]
+        .into_iter()
+        .collect();
+        let info = find_root_info(&source, None::<&StdHashSet>, Some(&cluster), &config).unwrap();
+        assert_eq!(info.case, DetectionCase::DependencyCluster);
+        assert_eq!(info.root, temp.path().join("scripts"));
+    }
+
+    #[test]
+    fn test_project_kind_precedence() {
+        assert_eq!(
+            ProjectKind::from_markers(&["package.json", "Cargo.toml"]),
+            ProjectKind::Rust
+        );
+        assert_eq!(ProjectKind::from_markers(&["go.mod"]), ProjectKind::Go);
+        assert_eq!(ProjectKind::from_markers(&[".git"]), ProjectKind::Unknown);
+    }
+
+    #[test]
+    fn test_project_kind_from_str() {
+        assert_eq!("node".parse::<ProjectKind>().unwrap(), ProjectKind::Node);
+        assert_eq!("JVM".parse::<ProjectKind>().unwrap(), ProjectKind::Jvm);
+        assert!("haskell".parse::<ProjectKind>().is_err());
+    }
+
+    #[test]
+    fn test_workspace_resolution_reports_member_and_workspace() {
+        let temp = setup_project(&[(".git", true), ("crates/core/src/lib.rs", false)]);
+        fs::write(
+            temp.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core\"\n",
+        )
+        .unwrap();
+
+        let source = temp.path().join("crates/core/src/lib.rs");
+
+        // Disabled by default
+        let config = Config::default();
+        let info =
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap();
+        assert_eq!(info.root, temp.path().join("crates/core"));
+        assert_eq!(info.workspace, None);
+
+        let config = Config::default().with_workspace_resolution(true);
+        let info =
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap();
+        assert_eq!(info.root, temp.path().join("crates/core"));
+        assert_eq!(
+            info.workspace,
+            Some(WorkspaceInfo {
+                root: temp.path().to_path_buf(),
+                kind: WorkspaceKind::Cargo,
+            })
+        );
+    }
+
+    #[test]
+    fn test_strategies_pick_different_roots() {
+        let temp = setup_project(&[
+            ("mono/.git", true),
+            ("mono/package.json", false),
+            ("mono/packages/api/package.json", false),
+            ("mono/packages/api/src/index.ts", false),
+        ]);
+        let source = temp.path().join("mono/packages/api/src/index.ts");
+        let root_with = |strategy: RootStrategy| {
+            let config = Config::default().with_strategy(strategy);
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap()
+        };
+
+        assert_eq!(
+            root_with(RootStrategy::Innermost).root,
+            temp.path().join("mono/packages/api")
+        );
+        assert_eq!(
+            root_with(RootStrategy::Outermost).root,
+            temp.path().join("mono")
+        );
+
+        let vcs = root_with(RootStrategy::Vcs);
+        assert_eq!(vcs.root, temp.path().join("mono"));
+        assert_eq!(vcs.markers, vec![".git".to_string()]);
+
+        let priority = root_with(RootStrategy::Priority(vec![
+            ".git".to_string(),
+            "package.json".to_string(),
+        ]));
+        assert_eq!(priority.root, temp.path().join("mono"));
+
+        // Markers missing from the ancestry are skipped
+        let priority = root_with(RootStrategy::Priority(vec![
+            "go.mod".to_string(),
+            "package.json".to_string(),
+        ]));
+        assert_eq!(priority.root, temp.path().join("mono/packages/api"));
+    }
+
+    #[test]
+    fn test_weak_marker_defers_to_heavier_ancestor() {
+        let temp = setup_project(&[
+            ("repo/.git", true),
+            ("repo/tools/CMakeLists.txt", false),
+            ("repo/tools/gen.cpp", false),
+            ("repo/lib/Cargo.toml", false),
+            ("repo/lib/CMakeLists.txt", false),
+            ("repo/lib/src/lib.rs", false),
+            ("solo/CMakeLists.txt", false),
+            ("solo/main.cpp", false),
+        ]);
+        let info = |path: &str, config: &Config| {
+            find_root_info(
+                &temp.path().join(path),
+                None::<&StdHashSet>,
+                None::<&StdHashSet>,
+                config,
+            )
+            .unwrap()
+        };
+        // Every default marker weighs the same: CMake subprojects are roots
+        let config = Config::default();
+        assert_eq!(
+            info("repo/tools/gen.cpp", &config).root,
+            temp.path().join("repo/tools")
+        );
+
+        let config = Config::default().with_weak_defaults();
+        let tools = info("repo/tools/gen.cpp", &config);
+        assert_eq!(tools.root, temp.path().join("repo"));
+        let decision = tools.decision.unwrap();
+        assert_eq!(decision.marker, ".git");
+        assert_eq!(decision.reason, DecisionReason::Outweighed);
+        assert_eq!(decision.displaced, Some(temp.path().join("repo/tools")));
+
+        // A weak marker next to a strong one is still a root
+        let lib = info("repo/lib/src/lib.rs", &config);
+        assert_eq!(lib.root, temp.path().join("repo/lib"));
+        let decision = lib.decision.unwrap();
+        assert_eq!(decision.marker, "Cargo.toml");
+        assert_eq!(decision.reason, DecisionReason::Innermost);
+
+        // Without anything heavier above, the weak marker wins
+        let solo = info("solo/main.cpp", &config);
+        assert_eq!(solo.root, temp.path().join("solo"));
+        assert_eq!(solo.decision.unwrap().weight, Marker::WEAK_WEIGHT);
+
+        // An explicit weight is kept
+        let config = Config::default()
+            .with_marker(Marker::new("CMakeLists.txt").with_weight(20))
+            .with_weak_defaults();
+        assert_eq!(
+            info("repo/tools/gen.cpp", &config).root,
+            temp.path().join("repo/tools")
+        );
+    }
+
+    #[test]
+    fn test_marker_predicates_skip_tooling_files() {
+        let temp = setup_project(&[
+            ("repo/.git", true),
+            ("repo/pyproject.toml", false),
+            ("repo/scripts/package.json", false),
+            ("repo/scripts/pyproject.toml", false),
+            ("repo/scripts/build.py", false),
+        ]);
+        fs::write(
+            temp.path().join("repo/pyproject.toml"),
+            "[project]\nname = \"repo\"\n",
+        )
+        .unwrap();
+        // Tooling-only configs: no package name, no [project] table
+        fs::write(
+            temp.path().join("repo/scripts/package.json"),
+            r#"{"prettier": {"semi": false}}"#,
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("repo/scripts/pyproject.toml"),
+            "[tool.black]\nline-length = 100\n",
+        )
+        .unwrap();
+
+        let source = temp.path().join("repo/scripts/build.py");
+
+        // By default markers only need to exist
+        assert_eq!(
+            find_root(
+                &source,
+                None::<&StdHashSet>,
+                None::<&StdHashSet>,
+                &Config::default()
+            ),
+            Some(temp.path().join("repo/scripts"))
+        );
+
+        let config = Config::default().with_builtin_predicates();
+        let info =
+            find_root_info(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config).unwrap();
+        assert_eq!(info.root, temp.path().join("repo"));
+        assert_eq!(
+            info.markers,
+            vec![".git".to_string(), "pyproject.toml".to_string()]
+        );
+
+        // Markers without predicates only need to exist
+        let config = Config::default()
+            .with_builtin_predicates()
+            .with_marker(Marker::new("package.json"));
+        assert_eq!(
+            find_root(&source, None::<&StdHashSet>, None::<&StdHashSet>, &config),
+            Some(temp.path().join("repo/scripts"))
+        );
+    }
+
+    #[test]
+    fn test_glob_markers_and_exclusions() {
+        let temp = setup_project(&[
+            ("repo/.git", true),
+            ("repo/App/App.sln", false),
+            ("repo/App/Program.cs", false),
+            ("repo/mypkg.egg-info/top_level.py", false),
+            ("repo/docs/api/generated/ref.py", false),
+            ("repo/docs/api/conf.py", false),
+            ("sdk/global.json", false),
+            ("sdk/tool.py", false),
+        ]);
+        // Glob markers list the directory, so the defaults have none
+        assert!(!Config::default().markers.iter().any(Marker::is_glob));
+        let mut config = Config::default()
+            .with_dotnet_markers()
+            .with_exclusions(&["docs/**/generated"]);
+
+        // A traversal anchors path globs at its root
+        let files: Vec<PathBuf> = traverse_and_detect(
+            &temp.path().join("repo"),
+            &config,
+            &TraversalOptions::default(),
+        )
+        .into_iter()
+        .map(|r| r.file)
+        .collect();
+        assert_eq!(files.len(), 3);
+        assert!(!files.iter().any(|f| f.ends_with("generated/ref.py")));
+
+        // Without an anchor a path glob matches the end of absolute paths
+        let generated = temp.path().join("repo/docs/api/generated/ref.py");
+        assert!(is_excluded(&generated, &config, None));
+        assert!(!is_excluded(
+            &temp.path().join("repo/docs/api/conf.py"),
+            &config,
+            None
+        ));
+
+        config
+            .exclusions
+            .anchor_at(&temp.path().join("repo").canonicalize().unwrap());
+        let root = |path: &str| {
+            find_root_info(
+                &temp.path().join(path),
+                None::<&StdHashSet>,
+                None::<&StdHashSet>,
+                &config,
+            )
+        };
+
+        let app = root("repo/App/Program.cs").unwrap();
+        assert_eq!(app.root, temp.path().join("repo/App"));
+        assert_eq!(app.markers, vec!["*.sln".to_string()]);
+        assert_eq!(app.kind, ProjectKind::Dotnet);
+
+        // Build settings alone make a root, but not a .NET project
+        let tool = root("sdk/tool.py").unwrap();
+        assert_eq!(tool.root, temp.path().join("sdk"));
+        assert_eq!(tool.decision.unwrap().weight, Marker::WEAK_WEIGHT);
+        assert_eq!(tool.kind, ProjectKind::Unknown);
+
+        // `*.egg-info` is a default exclusion
+        assert!(root("repo/mypkg.egg-info/top_level.py").is_none());
+        assert!(root("repo/docs/api/generated/ref.py").is_none());
+        assert_eq!(
+            root("repo/docs/api/conf.py").unwrap().root,
+            temp.path().join("repo")
+        );
+    }
+
+    #[test]
+    fn test_strategy_from_str() {
+        assert_eq!("vcs".parse::<RootStrategy>().unwrap(), RootStrategy::Vcs);
+        assert_eq!(
+            "priority:Cargo.toml, .git".parse::<RootStrategy>().unwrap(),
+            RootStrategy::Priority(vec!["Cargo.toml".to_string(), ".git".to_string()])
+        );
+        assert!("priority:".parse::<RootStrategy>().is_err());
+        assert!("closest".parse::<RootStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_root_chain_innermost_to_outermost() {
+        let temp = setup_project(&[
+            ("mono/.git", true),
+            ("mono/packages/api/package.json", false),
+            ("mono/packages/api/src/index.ts", false),
+            ("mono/node_modules/dep/package.json", false),
+            ("mono/node_modules/dep/index.js", false),
+        ]);
+
+        let config = Config::default();
+        let chain = find_root_chain(&temp.path().join("mono/packages/api/src/index.ts"), &config);
+        let roots: Vec<_> = chain.iter().map(|info| info.root.clone()).collect();
+        assert_eq!(
+            roots,
+            vec![
+                temp.path().join("mono/packages/api"),
+                temp.path().join("mono"),
+            ]
+        );
+        assert_eq!(chain[0].kind, ProjectKind::Node);
+        assert_eq!(chain[1].markers, vec![".git".to_string()]);
+
+        // Excluded files have no chain
+        let excluded = temp.path().join("mono/node_modules/dep/index.js");
+        assert!(find_root_chain(&excluded, &config).is_empty());
+    }
+
     // ==================== TRAVERSAL TESTS ====================
 
     #[test]
@@ -1281,6 +2505,44 @@ mod tests {
         assert!(results[0].file.ends_with("main.rs"));
     }
 
+    #[test]
+    fn test_traverse_respects_ignore_files() {
+        let temp = setup_project(&[
+            ("repo/.git/info", true),
+            ("repo/Cargo.toml", false),
+            ("repo/src/main.rs", false),
+            ("repo/src/generated/schema.rs", false),
+            ("repo/scratch/notes.rs", false),
+            ("repo/crates/a/Cargo.toml", false),
+            ("repo/crates/a/src/lib.rs", false),
+            ("repo/crates/a/src/bench.rs", false),
+        ]);
+        let repo = temp.path().join("repo");
+        fs::write(repo.join(".gitignore"), "generated/\n").unwrap();
+        fs::write(repo.join(".git/info/exclude"), "/scratch\n").unwrap();
+        fs::write(repo.join("crates/a/.rootdetectignore"), "bench.rs\n").unwrap();
+
+        let options = TraversalOptions::default()
+            .with_extensions(&["rs"])
+            .with_ignore_files(true);
+        let mut files: Vec<PathBuf> = traverse_and_detect(&repo, &Config::default(), &options)
+            .into_iter()
+            .map(|r| r.file)
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![repo.join("crates/a/src/lib.rs"), repo.join("src/main.rs")]
+        );
+
+        // Off by default
+        let options = TraversalOptions::default().with_extensions(&["rs"]);
+        assert_eq!(
+            traverse_and_detect(&repo, &Config::default(), &options).len(),
+            5
+        );
+    }
+
     #[test]
     fn test_traverse_orphan_files() {
         let temp = setup_project(&[("scripts/util.py", false), ("scripts/helper.py", false)]);
@@ -0,0 +1,122 @@
+//! Detection from a path listing alone.
+//!
+//! When all we have is a list of paths (`git ls-files`, a build graph, a
+//! remote index), the listing itself is the tree: a directory exists if some
+//! listed path is below it, and a marker is present if it is listed. Nothing
+//! is read from disk, so:
+//!
+//! - content predicates cannot be checked and markers count by name alone;
+//! - directories that are never listed (like `.git`) are not seen, unless the
+//!   listing names them;
+//! - workspace manifests cannot be read, so no workspaces are reported.
+//!
+//! Results are otherwise those of [`crate::find_roots_batch`] over a checkout
+//! holding exactly the listed files, including the orphanage rule.
+
+use crate::{find_root_infos_batch, Config, MemoryFs, RootInfo};
+use std::path::{Path, PathBuf};
+
+/// Detect roots for every path of a listing, without touching the filesystem.
+///
+/// Relative paths are taken relative to the listing's top directory, which
+/// is reported as the empty path.
+#[must_use]
+pub fn find_roots_from_listing<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    config: &Config,
+) -> Vec<(&'a Path, Option<PathBuf>)> {
+    find_root_infos_from_listing(paths, config)
+        .into_iter()
+        .map(|(path, info)| (path, info.map(|i| i.root)))
+        .collect()
+}
+
+/// Variant of [`find_roots_from_listing`] reporting detection details
+#[must_use]
+pub fn find_root_infos_from_listing<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    config: &Config,
+) -> Vec<(&'a Path, Option<RootInfo>)> {
+    let paths: Vec<&'a Path> = paths.into_iter().collect();
+
+    let mut tree = MemoryFs::new();
+    for path in &paths {
+        tree.add_file(path, "");
+    }
+
+    let mut listing_config = config.clone().with_filesystem(tree);
+    listing_config.markers = config
+        .markers
+        .iter()
+        .cloned()
+        .map(|mut marker| {
+            marker.predicates.clear();
+            marker
+        })
+        .collect();
+    listing_config.resolve_workspaces = false;
+
+    find_root_infos_batch(paths, &listing_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_roots_batch, DetectionCase};
+    use std::fs;
+    use tempfile::TempDir;
+
+    const LISTING: &[&str] = &[
+        "repo/.git/HEAD",
+        "repo/Cargo.toml",
+        "repo/src/main.rs",
+        "repo/web/package.json",
+        "repo/web/src/index.ts",
+        "repo/.venv/lib/site.py",
+        "scripts/a/one.py",
+        "scripts/b/two.py",
+        "notes/todo.md",
+    ];
+
+    #[test]
+    fn test_listing_matches_checkout() {
+        let temp = TempDir::new().unwrap();
+        let files: Vec<PathBuf> = LISTING.iter().map(|p| temp.path().join(p)).collect();
+        for file in &files {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            // Contents that meet the built-in predicates on disk
+            let contents = if file.ends_with("package.json") {
+                r#"{"name": "web"}"#
+            } else {
+                ""
+            };
+            fs::write(file, contents).unwrap();
+        }
+
+        let config = Config::default();
+        let on_disk = find_roots_batch(files.iter().map(PathBuf::as_path), &config);
+        let listed = find_roots_from_listing(files.iter().map(PathBuf::as_path), &config);
+        assert_eq!(listed, on_disk);
+        assert_eq!(listed[2].1, Some(temp.path().join("repo")));
+        assert_eq!(listed[5].1, None);
+        assert_eq!(listed[6].1, Some(temp.path().to_path_buf()));
+    }
+
+    #[test]
+    fn test_relative_listing_without_filesystem() {
+        // None of these exist: every answer comes from the listing
+        let paths: Vec<&Path> = LISTING.iter().map(Path::new).collect();
+        let results = find_root_infos_from_listing(paths, &Config::default());
+
+        let root = |i: usize| results[i].1.as_ref().map(|info| info.root.clone());
+        assert_eq!(root(1), Some(PathBuf::from("repo")));
+        assert_eq!(root(4), Some(PathBuf::from("repo/web")));
+        assert_eq!(root(5), None);
+        // Orphans group up to the listing's top directory
+        assert_eq!(root(7), Some(PathBuf::new()));
+
+        let info = results[4].1.as_ref().unwrap();
+        assert_eq!(info.case, DetectionCase::Marker);
+        assert_eq!(info.markers, vec!["package.json".to_string()]);
+    }
+}
@@ -8,16 +8,27 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use project_root_detector::{
-    discover_roots, find_roots_batch, traverse_and_detect, Config, TraversalOptions,
-    TraversalResult,
+    affected_roots, cache_stats, clear_cache, default_cache_dir, discover_roots, find_root_chain,
+    find_root_infos_batch_with_cache, find_root_infos_from_listing, git_changed_files,
+    git_merge_base, git_tracked_files, global_excludes_file, is_excluded, load_workspace,
+    traverse_and_detect, traverse_archive, traverse_iter, BuildPackage, Config, DetectionCase,
+    MarkerCache, MarkerDecision, PackageInfo, ProjectKind, RootInfo, RootStrategy,
+    TraversalOptions, TraversalResult, WorkspaceInfo,
 };
 use serde::Serialize;
-use std::collections::HashSet;
-use std::io::{self, BufRead};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsStr;
+use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::process::ExitCode;
+use std::process::{self, ExitCode, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Detect project root directories from source file paths.
+// Global flags are independent switches, not state
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Debug)]
 #[command(name = "project-root-detector")]
 #[command(version, about, long_about = None)]
@@ -32,16 +43,51 @@ struct Args {
     /// Exit with code 1 if any file is excluded
     #[arg(long, global = true)]
     check: bool,
+
+    /// Config file to use instead of discovering `.rootdetect.toml` / `.rootdetect.json`
+    #[arg(long, global = true, value_name = "FILE", env = "ROOTDETECT_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Also report the workspace enclosing each root and its package name and
+    /// import directory (Cargo, npm, Python, ...)
+    #[arg(long, global = true)]
+    workspaces: bool,
+
+    /// Root strategy: innermost, outermost, vcs, build_system, or priority:<marker>,<marker>,...
+    #[arg(long, global = true, value_name = "STRATEGY")]
+    strategy: Option<RootStrategy>,
+
+    /// Let CMakeLists.txt & co. defer to a heavier marker in an enclosing
+    /// directory (like `weak_defaults = true` in a config file)
+    #[arg(long, global = true)]
+    weak_defaults: bool,
+
+    /// Check the contents of default markers, so that e.g. a package.json
+    /// without `name` or `workspaces` is not a root (like
+    /// `builtin_predicates = true` in a config file)
+    #[arg(long, global = true)]
+    builtin_predicates: bool,
+
+    /// Detect .NET solutions and projects (*.sln, *.csproj, *.fsproj), with
+    /// weak Directory.Build.props and global.json markers (like `dotnet = true`
+    /// in a config file)
+    #[arg(long, global = true)]
+    dotnet: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Traverse a directory tree and detect project roots for all source files
     Traverse {
-        /// Directory to traverse
+        /// Directory to traverse (or archive, with --archive)
         #[arg(value_name = "DIR")]
         directory: PathBuf,
 
+        /// Treat DIR as a .tar, .tar.gz/.tgz or .zip archive and report
+        /// archive-internal paths
+        #[arg(long)]
+        archive: bool,
+
         /// File extensions to include (e.g., rs, py, js). If not specified, all files are included.
         #[arg(short, long, value_delimiter = ',')]
         extensions: Option<Vec<String>>,
@@ -53,6 +99,17 @@ enum Command {
         /// Only show unique project roots (not individual files)
         #[arg(long)]
         roots_only: bool,
+
+        /// Skip paths ignored by .gitignore, .git/info/exclude, the global git
+        /// excludes file and .rootdetectignore
+        #[arg(long)]
+        ignore_files: bool,
+
+        /// Worker threads (0 = one per CPU). Without -j, or with -j 1, per-file
+        /// results are printed as soon as their root is known; with more
+        /// threads they are printed in path order after the walk
+        #[arg(short = 'j', long)]
+        threads: Option<usize>,
     },
 
     /// Detect roots for explicit file paths
@@ -64,7 +121,100 @@ enum Command {
         /// Read file paths from stdin (one per line)
         #[arg(long)]
         batch: bool,
+
+        /// Treat the paths as a complete listing (e.g. `git ls-files`) and
+        /// never touch the filesystem; content predicates are not checked and
+        /// --cache has no effect
+        #[arg(long)]
+        listing: bool,
+
+        /// Analyze the files tracked in the git index under each FILE
+        /// directory (default: the current directory), without running git
+        #[arg(long, conflicts_with = "batch")]
+        git: bool,
+
+        /// Reuse the marker sets and roots of directories from earlier runs,
+        /// kept in $XDG_CACHE_HOME (also enabled by `ROOTDETECT_CACHE=1`)
+        #[arg(long)]
+        cache: bool,
+    },
+
+    /// Print the project roots affected by a change
+    Affected {
+        /// Changed paths, deleted ones included (read from stdin if none given)
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+
+        /// Take the changed paths from the git trees of two revisions
+        /// (`main..HEAD`; TO defaults to HEAD), read from .git. `main...HEAD`
+        /// compares HEAD with its merge base with main
+        #[arg(long, value_name = "FROM[..TO]", conflicts_with = "paths")]
+        diff: Option<String>,
+    },
+
+    /// List every enclosing root of each file, innermost to outermost
+    Ancestors {
+        /// Source files to analyze
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Read file paths from stdin (one per line)
+        #[arg(long)]
+        batch: bool,
+    },
+
+    /// Show the workspace defined at a directory and list its members
+    Workspace {
+        /// Workspace root directory
+        #[arg(value_name = "DIR", default_value = ".")]
+        directory: PathBuf,
+    },
+
+    /// Run a command in every project root found under a directory
+    Exec {
+        /// Directory to search for project roots
+        #[arg(value_name = "DIR")]
+        directory: PathBuf,
+
+        /// Command and arguments to run in each root
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+
+        /// Only roots of these kinds (rust, node, python, go, jvm, ...)
+        #[arg(short, long, value_delimiter = ',')]
+        kind: Vec<ProjectKind>,
+
+        /// Commands to run at once (0 = one per CPU)
+        #[arg(short = 'j', long, default_value_t = 1)]
+        jobs: usize,
+
+        /// Maximum traversal depth (0 = only the start directory)
+        #[arg(short = 'd', long)]
+        max_depth: Option<usize>,
+
+        /// Skip paths ignored by .gitignore and .rootdetectignore
+        #[arg(long)]
+        ignore_files: bool,
+
+        /// Also run in orphanages, the directories grouping files that have
+        /// no project marker
+        #[arg(long)]
+        include_orphanages: bool,
     },
+
+    /// Manage the persistent cache used by `files --cache`
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Delete all cache files
+    Clear,
+    /// Show the cache location, file count, entries and size
+    Stats,
 }
 
 /// Result for a single file's root detection
@@ -73,10 +223,84 @@ struct FileResult {
     file: PathBuf,
     #[serde(skip_serializing_if = "Option::is_none")]
     root: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    markers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case: Option<DetectionCase>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ProjectKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decision: Option<MarkerDecision>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace: Option<WorkspaceInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<PackageInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_package: Option<BuildPackage>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    excluded: bool,
+}
+
+impl FileResult {
+    fn new(file: PathBuf, info: Option<RootInfo>) -> Self {
+        match info {
+            Some(info) => Self {
+                file,
+                root: Some(info.root),
+                markers: info.markers,
+                case: Some(info.case),
+                kind: Some(info.kind),
+                decision: info.decision,
+                workspace: info.workspace,
+                package: info.package,
+                build_package: info.build_package,
+                excluded: false,
+            },
+            None => Self {
+                file,
+                root: None,
+                markers: Vec::new(),
+                case: None,
+                kind: None,
+                decision: None,
+                workspace: None,
+                package: None,
+                build_package: None,
+                excluded: true,
+            },
+        }
+    }
+}
+
+/// Chain of enclosing roots for a single file
+#[derive(Serialize)]
+struct AncestorsResult {
+    file: PathBuf,
+    roots: Vec<RootInfo>,
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     excluded: bool,
 }
 
+/// Outcome of the command in one root
+#[derive(Serialize)]
+struct ExecResult {
+    root: PathBuf,
+    kind: ProjectKind,
+    /// Exit code, or None if the command could not start or was killed
+    status: Option<i32>,
+    success: bool,
+    duration_ms: u128,
+}
+
+/// Summary of an `exec` run
+#[derive(Serialize)]
+struct ExecSummary {
+    command: Vec<String>,
+    results: Vec<ExecResult>,
+    succeeded: usize,
+    failed: usize,
+}
+
 /// Result for unique roots discovery
 #[derive(Serialize)]
 struct RootsResult {
@@ -97,103 +321,611 @@ fn collect_files_from_stdin() -> Result<Vec<PathBuf>> {
         .collect()
 }
 
-fn run_traverse(
-    directory: &Path,
+/// File arguments, or stdin when `--batch` is set or no files are given
+fn input_files(files: &[PathBuf], batch: bool) -> Result<Vec<PathBuf>> {
+    let files: Vec<PathBuf> = if batch || files.is_empty() {
+        collect_files_from_stdin()?
+    } else {
+        files.to_vec()
+    };
+
+    if files.is_empty() {
+        anyhow::bail!("No files provided");
+    }
+    Ok(files)
+}
+
+/// Tracked files under each directory, or under the current one
+fn git_files(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let current = [PathBuf::from(".")];
+    let dirs = if dirs.is_empty() { &current[..] } else { dirs };
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        files.extend(without_dot_prefix(git_tracked_files(dir)?));
+    }
+    Ok(files)
+}
+
+/// `./src/main.rs` reads better as `src/main.rs`
+fn without_dot_prefix(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix(".")
+                .map_or(path.clone(), Path::to_path_buf)
+        })
+        .collect()
+}
+
+/// Load the explicit config file, or discover one from `start_dir` upwards,
+/// then apply command-line overrides
+fn load_config(args: &Args, start_dir: &Path) -> Result<Config> {
+    let mut config = args
+        .config
+        .as_deref()
+        .map_or_else(|| Config::discover(start_dir), Config::from_file)
+        .context("Failed to load configuration")?;
+
+    if args.workspaces {
+        config.resolve_workspaces = true;
+    }
+    if let Some(strategy) = &args.strategy {
+        config.strategy = strategy.clone();
+    }
+    if args.weak_defaults {
+        config = config.with_weak_defaults();
+    }
+    if args.builtin_predicates {
+        config = config.with_builtin_predicates();
+    }
+    if args.dotnet {
+        config = config.with_dotnet_markers();
+    }
+    Ok(config)
+}
+
+fn traversal_options(
     extensions: Option<&Vec<String>>,
     max_depth: Option<usize>,
-    roots_only: bool,
-    json: bool,
-    check: bool,
-) -> Result<bool> {
-    let config = Config::default();
-
-    let mut options = TraversalOptions::default();
+    ignore_files: bool,
+    threads: usize,
+) -> TraversalOptions {
+    let mut options = TraversalOptions::default()
+        .with_ignore_files(ignore_files)
+        .with_global_excludes_file(ignore_files.then(global_excludes_file).flatten())
+        .with_threads(threads);
     if let Some(exts) = extensions {
         options.extensions = exts.iter().cloned().collect();
     }
     if let Some(depth) = max_depth {
         options.max_depth = Some(depth);
     }
+    options
+}
 
+fn run_traverse(
+    config: &Config,
+    directory: &Path,
+    options: &TraversalOptions,
+    roots_only: bool,
+    json: bool,
+    check: bool,
+) -> Result<bool> {
     if roots_only {
-        let roots: HashSet<PathBuf> = discover_roots(directory, &config, &options);
+        let roots: HashSet<PathBuf> = discover_roots(directory, config, options);
         let mut roots_vec: Vec<PathBuf> = roots.into_iter().collect();
         roots_vec.sort();
+        output_roots(roots_vec, json)?;
 
-        if json {
-            let result = RootsResult {
-                count: roots_vec.len(),
-                roots: roots_vec,
-            };
-            let json_str =
-                serde_json::to_string_pretty(&result).context("Failed to serialize to JSON")?;
-            println!("{json_str}");
+        Ok(false) // roots_only mode doesn't track exclusions
+    } else {
+        let results = traversal_results(directory, config, options);
+        let any_excluded = write_traversal(results, json, &mut io::stdout().lock())?;
+
+        Ok(check && any_excluded)
+    }
+}
+
+/// Threads for `traverse`: per-file output streams unless threads are asked
+/// for; a roots list only comes out at the end, so it uses every CPU
+fn traverse_threads(threads: Option<usize>, roots_only: bool) -> usize {
+    threads.unwrap_or_else(|| usize::from(!roots_only))
+}
+
+/// Per-file results of a traversal. A single thread streams them as their
+/// roots are decided; a parallel walk finishes first, then yields them in
+/// path order.
+fn traversal_results<'a>(
+    directory: &Path,
+    config: &'a Config,
+    options: &'a TraversalOptions,
+) -> Box<dyn Iterator<Item = TraversalResult> + 'a> {
+    if options.threads == 1 {
+        Box::new(traverse_iter(directory, config, options))
+    } else {
+        Box::new(traverse_and_detect(directory, config, options).into_iter())
+    }
+}
+
+/// Write each result as soon as the iterator yields it; returns whether any
+/// file was excluded
+fn write_traversal(
+    results: impl Iterator<Item = TraversalResult>,
+    json: bool,
+    out: &mut impl Write,
+) -> Result<bool> {
+    let mut any_excluded = false;
+    let mut json_array = json.then(JsonArrayWriter::default);
+    for r in results {
+        let result = FileResult::new(r.file, r.info);
+        any_excluded |= result.excluded;
+        match &mut json_array {
+            Some(writer) => writer.push(out, &result)?,
+            None => writeln!(out, "{}", file_result_line(&result))?,
+        }
+        out.flush()?;
+    }
+    if let Some(writer) = json_array {
+        writer.finish(out)?;
+    }
+    Ok(any_excluded)
+}
+
+fn run_traverse_archive(
+    config: &Config,
+    archive: &Path,
+    options: &TraversalOptions,
+    roots_only: bool,
+    json: bool,
+    check: bool,
+) -> Result<bool> {
+    let results = traverse_archive(archive, config, options)?;
+
+    if roots_only {
+        let roots: BTreeSet<PathBuf> = results.into_iter().filter_map(|r| r.root).collect();
+        output_roots(roots.into_iter().collect(), json)?;
+        return Ok(false);
+    }
+
+    let results: Vec<FileResult> = results
+        .into_iter()
+        .map(|r| FileResult::new(r.file, r.info))
+        .collect();
+    output_file_results(&results, json)?;
+    Ok(check && results.iter().any(|r| r.excluded))
+}
+
+fn output_roots(roots: Vec<PathBuf>, json: bool) -> Result<()> {
+    if json {
+        let result = RootsResult {
+            count: roots.len(),
+            roots,
+        };
+        let json_str =
+            serde_json::to_string_pretty(&result).context("Failed to serialize to JSON")?;
+        println!("{json_str}");
+    } else {
+        for root in &roots {
+            println!("{}", display_root(root));
+        }
+    }
+    Ok(())
+}
+
+/// Prints a pretty JSON array one element at a time
+#[derive(Default)]
+struct JsonArrayWriter {
+    count: usize,
+}
+
+impl JsonArrayWriter {
+    fn push<T: Serialize>(&mut self, out: &mut impl Write, item: &T) -> Result<()> {
+        let element = serde_json::to_string_pretty(item).context("Failed to serialize to JSON")?;
+        let indented = element.replace('\n', "\n  ");
+        if self.count == 0 {
+            write!(out, "[\n  {indented}")?;
         } else {
-            for root in &roots_vec {
-                println!("{}", root.display());
-            }
+            write!(out, ",\n  {indented}")?;
         }
+        self.count += 1;
+        Ok(())
+    }
 
-        Ok(false) // roots_only mode doesn't track exclusions
+    fn finish(self, out: &mut impl Write) -> io::Result<()> {
+        if self.count == 0 {
+            writeln!(out, "[]")
+        } else {
+            writeln!(out, "\n]")
+        }
+    }
+}
+
+fn run_files(
+    config: &Config,
+    files: &[PathBuf],
+    batch: bool,
+    listing: bool,
+    git: bool,
+    marker_cache: &MarkerCache,
+    args: &Args,
+) -> Result<bool> {
+    let files = if git {
+        git_files(files)?
+    } else {
+        input_files(files, batch)?
+    };
+    let paths = files.iter().map(PathBuf::as_path);
+
+    let results = if listing {
+        // Listed paths are relative to the current directory
+        let mut config = config.clone();
+        if let Ok(cwd) = std::env::current_dir() {
+            config.exclusions.add_alias(Path::new(""), &cwd);
+        }
+        find_root_infos_from_listing(paths, &config)
+    } else {
+        let results = find_root_infos_batch_with_cache(paths, config, marker_cache);
+        // A cache that cannot be written only costs speed next time
+        if let Err(e) = marker_cache.save() {
+            eprintln!("Warning: {e}");
+        }
+        results
+    };
+
+    let file_results: Vec<FileResult> = results
+        .into_iter()
+        .map(|(path, info)| FileResult::new(path.to_path_buf(), info))
+        .collect();
+    let any_excluded = file_results.iter().any(|r| r.excluded);
+
+    output_file_results(&file_results, args.json)?;
+
+    Ok(args.check && any_excluded)
+}
+
+fn run_affected(
+    config: &Config,
+    paths: &[PathBuf],
+    diff: Option<&str>,
+    json: bool,
+) -> Result<bool> {
+    let paths = match diff {
+        Some(range) => {
+            let (from, to) = diff_revisions(Path::new("."), range)?;
+            without_dot_prefix(git_changed_files(Path::new("."), &from, &to)?)
+        }
+        None => input_files(paths, false)?,
+    };
+
+    let roots = affected_roots(paths.iter().map(PathBuf::as_path), config);
+    output_roots(roots, json)?;
+    Ok(false)
+}
+
+/// Revisions to compare for `--diff` in the repository of `dir`: `A..B`
+/// compares A with B and `A...B` the merge base of A and B with B, like
+/// `git diff`. An omitted side is HEAD.
+fn diff_revisions(dir: &Path, range: &str) -> Result<(String, String)> {
+    let side = |revision: &str| {
+        if revision.is_empty() {
+            "HEAD"
+        } else {
+            revision
+        }
+        .to_string()
+    };
+    if let Some((a, b)) = range.split_once("...") {
+        let base = git_merge_base(dir, &side(a), &side(b))?;
+        return Ok((base, side(b)));
+    }
+    let (from, to) = range.split_once("..").unwrap_or((range, "HEAD"));
+    Ok((side(from), side(to)))
+}
+
+/// Roots under `directory` for `exec`, sorted: those found through a marker or
+/// a dependency cluster, plus orphanages if asked, of the given kinds (any if
+/// none are given)
+fn exec_roots(
+    directory: &Path,
+    config: &Config,
+    options: &TraversalOptions,
+    kinds: &[ProjectKind],
+    include_orphanages: bool,
+) -> Vec<(PathBuf, ProjectKind)> {
+    let mut roots: BTreeMap<PathBuf, ProjectKind> = BTreeMap::new();
+    for info in traverse_and_detect(directory, config, options)
+        .into_iter()
+        .filter_map(|r| r.info)
+        .filter(|info| include_orphanages || info.case != DetectionCase::Orphanage)
+    {
+        roots.entry(info.root).or_insert(info.kind);
+    }
+    roots
+        .into_iter()
+        .filter(|(_, kind)| kinds.is_empty() || kinds.contains(kind))
+        .collect()
+}
+
+fn run_exec(
+    roots: &[(PathBuf, ProjectKind)],
+    command: &[String],
+    jobs: usize,
+    json: bool,
+) -> Result<bool> {
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        n => n,
+    };
+    let results = exec_in_roots(roots, command, jobs, json);
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    let summary = ExecSummary {
+        command: command.to_vec(),
+        succeeded: results.len() - failed,
+        failed,
+        results,
+    };
+    if json {
+        let json_str =
+            serde_json::to_string_pretty(&summary).context("Failed to serialize to JSON")?;
+        println!("{json_str}");
     } else {
-        let results: Vec<TraversalResult> = traverse_and_detect(directory, &config, &options);
-
-        let mut any_excluded = false;
-        let file_results: Vec<FileResult> = results
-            .into_iter()
-            .map(|r| {
-                let excluded = r.root.is_none();
-                if excluded {
-                    any_excluded = true;
+        for result in &summary.results {
+            let outcome = match (result.success, result.status) {
+                (true, _) => "ok".to_string(),
+                (false, Some(code)) => format!("failed (exit {code})"),
+                (false, None) => "failed".to_string(),
+            };
+            println!(
+                "{}: {outcome} in {:.1}s",
+                display_root(&result.root),
+                Duration::from_millis(u64::try_from(result.duration_ms).unwrap_or(u64::MAX))
+                    .as_secs_f64()
+            );
+        }
+        println!(
+            "{} root(s): {} succeeded, {} failed",
+            summary.results.len(),
+            summary.succeeded,
+            summary.failed
+        );
+    }
+
+    Ok(failed > 0)
+}
+
+/// Run `command` in each root, `jobs` at a time; results are in root order
+fn exec_in_roots(
+    roots: &[(PathBuf, ProjectKind)],
+    command: &[String],
+    jobs: usize,
+    quiet_stdout: bool,
+) -> Vec<ExecResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(roots.len()));
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, roots.len().max(1)) {
+            scope.spawn(|| {
+                while let Some((root, kind)) = roots.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = exec_in_root(root, *kind, command, quiet_stdout);
+                    if let Ok(mut results) = results.lock() {
+                        results.push(result);
+                    }
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_default();
+    results.sort_by(|a, b| a.root.cmp(&b.root));
+    results
+}
+
+/// Run `command` in `root`, relaying its output line by line prefixed with
+/// the root; with `quiet_stdout` its stdout goes to stderr too
+fn exec_in_root(
+    root: &Path,
+    kind: ProjectKind,
+    command: &[String],
+    quiet_stdout: bool,
+) -> ExecResult {
+    let prefix = format!("[{}] ", display_root(root));
+    let start = Instant::now();
+    let spawned = process::Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let status = match spawned {
+        Ok(mut child) => {
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+            thread::scope(|scope| {
+                if let Some(stdout) = stdout {
+                    scope.spawn(|| relay_lines(stdout, &prefix, quiet_stdout));
                 }
-                FileResult {
-                    file: r.file,
-                    root: r.root,
-                    excluded,
+                if let Some(stderr) = stderr {
+                    scope.spawn(|| relay_lines(stderr, &prefix, true));
                 }
-            })
-            .collect();
+            });
+            child.wait().ok().and_then(|status| status.code())
+        }
+        Err(e) => {
+            eprintln!("{prefix}failed to run {}: {e}", command[0]);
+            None
+        }
+    };
 
-        output_file_results(&file_results, json)?;
+    ExecResult {
+        root: root.to_path_buf(),
+        kind,
+        status,
+        success: status == Some(0),
+        duration_ms: start.elapsed().as_millis(),
+    }
+}
 
-        Ok(check && any_excluded)
+/// Copy lines from `reader` to stdout (or stderr), each after `prefix`
+fn relay_lines(reader: impl io::Read, prefix: &str, to_stderr: bool) {
+    let mut reader = io::BufReader::new(reader);
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if to_stderr {
+            eprintln!("{prefix}{text}");
+        } else {
+            println!("{prefix}{text}");
+        }
+        line.clear();
     }
 }
 
-fn run_files(files: &[PathBuf], batch: bool, json: bool, check: bool) -> Result<bool> {
-    let config = Config::default();
+/// Root chain of `file`, with the empty root of a relative path shown as `.`
+fn ancestors_result(file: PathBuf, config: &Config) -> AncestorsResult {
+    let mut roots = find_root_chain(&file, config);
+    for info in &mut roots {
+        if info.root.as_os_str().is_empty() {
+            info.root = PathBuf::from(".");
+        }
+    }
+    AncestorsResult {
+        roots,
+        excluded: is_excluded(&file, config, None),
+        file,
+    }
+}
 
-    let files: Vec<PathBuf> = if batch || files.is_empty() {
-        collect_files_from_stdin()?
+fn run_ancestors(
+    config: &Config,
+    files: &[PathBuf],
+    batch: bool,
+    json: bool,
+    check: bool,
+) -> Result<bool> {
+    let files = input_files(files, batch)?;
+
+    let results: Vec<AncestorsResult> = files
+        .into_iter()
+        .map(|file| ancestors_result(file, config))
+        .collect();
+
+    if json {
+        let json_str =
+            serde_json::to_string_pretty(&results).context("Failed to serialize to JSON")?;
+        println!("{json_str}");
     } else {
-        files.to_vec()
+        for result in &results {
+            if result.excluded {
+                println!("{} -> (excluded)", result.file.display());
+            } else if result.roots.is_empty() {
+                println!("{} -> (no markers)", result.file.display());
+            } else {
+                let chain: Vec<String> = result
+                    .roots
+                    .iter()
+                    .map(|info| info.root.display().to_string())
+                    .collect();
+                println!("{} -> {}", result.file.display(), chain.join(" -> "));
+            }
+        }
+    }
+
+    Ok(check && results.iter().any(|r| r.excluded))
+}
+
+fn run_workspace(config: &Config, directory: &Path, json: bool) -> Result<bool> {
+    let root = directory
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", directory.display()))?;
+    let Some(workspace) = load_workspace(&root, config) else {
+        anyhow::bail!("No workspace defined in {}", root.display());
     };
 
-    if files.is_empty() {
-        anyhow::bail!("No files provided");
+    if json {
+        let json_str =
+            serde_json::to_string_pretty(&workspace).context("Failed to serialize to JSON")?;
+        println!("{json_str}");
+    } else {
+        for member in &workspace.members {
+            println!("{}", member.display());
+        }
     }
 
-    let results = find_roots_batch(files.iter().map(PathBuf::as_path), &config);
+    Ok(false)
+}
 
-    let mut any_excluded = false;
-    let file_results: Vec<FileResult> = results
-        .into_iter()
-        .map(|(path, root)| {
-            let excluded = root.is_none();
-            if excluded {
-                any_excluded = true;
+/// Cache statistics for display
+#[derive(Serialize)]
+struct CacheStatsResult {
+    directory: PathBuf,
+    files: usize,
+    entries: usize,
+    roots: usize,
+    bytes: u64,
+}
+
+/// Whether `files` uses the persistent cache: `--cache`, or a boolish
+/// `ROOTDETECT_CACHE` value
+fn cache_enabled(flag: bool, env: Option<&OsStr>) -> Result<bool> {
+    let Some(value) = env.filter(|_| !flag) else {
+        return Ok(flag);
+    };
+    match value.to_string_lossy().to_lowercase().as_str() {
+        "y" | "yes" | "t" | "true" | "on" | "1" => Ok(true),
+        "" | "n" | "no" | "f" | "false" | "off" | "0" => Ok(false),
+        other => anyhow::bail!("Invalid ROOTDETECT_CACHE value {other:?}: expected true or false"),
+    }
+}
+
+fn run_cache(action: &CacheAction, json: bool) -> Result<bool> {
+    let Some(directory) = default_cache_dir() else {
+        anyhow::bail!("No cache directory: neither XDG_CACHE_HOME nor HOME is set");
+    };
+
+    match action {
+        CacheAction::Clear => {
+            let removed = clear_cache(&directory).context("Failed to clear cache")?;
+            if json {
+                let json_str =
+                    serde_json::to_string_pretty(&serde_json::json!({ "removed": removed }))
+                        .context("Failed to serialize to JSON")?;
+                println!("{json_str}");
+            } else {
+                println!(
+                    "Removed {removed} cache file(s) from {}",
+                    directory.display()
+                );
             }
-            FileResult {
-                file: path.to_path_buf(),
-                root,
-                excluded,
+        }
+        CacheAction::Stats => {
+            let stats = cache_stats(&directory);
+            if json {
+                let result = CacheStatsResult {
+                    directory,
+                    files: stats.files,
+                    entries: stats.entries,
+                    roots: stats.roots,
+                    bytes: stats.bytes,
+                };
+                let json_str =
+                    serde_json::to_string_pretty(&result).context("Failed to serialize to JSON")?;
+                println!("{json_str}");
+            } else {
+                println!("Directory: {}", directory.display());
+                println!("Files:     {}", stats.files);
+                println!("Entries:   {}", stats.entries);
+                println!("Roots:     {}", stats.roots);
+                println!("Size:      {} bytes", stats.bytes);
             }
-        })
-        .collect();
-
-    output_file_results(&file_results, json)?;
+        }
+    }
 
-    Ok(check && any_excluded)
+    Ok(false)
 }
 
 fn output_file_results(results: &[FileResult], json: bool) -> Result<()> {
@@ -203,32 +935,117 @@ fn output_file_results(results: &[FileResult], json: bool) -> Result<()> {
         println!("{json_str}");
     } else {
         for result in results {
-            result.root.as_ref().map_or_else(
-                || println!("{} -> (excluded)", result.file.display()),
-                |r| println!("{} -> {}", result.file.display(), r.display()),
-            );
+            println!("{}", file_result_line(result));
         }
     }
     Ok(())
 }
 
+fn file_result_line(result: &FileResult) -> String {
+    let root = result.root.as_deref().map_or_else(
+        || "(excluded)".to_string(),
+        |root| display_root(root).to_string(),
+    );
+    let label = result
+        .build_package
+        .as_ref()
+        .map_or_else(String::new, |package| format!(" {}", package.label));
+    format!("{} -> {root}{label}", result.file.display())
+}
+
+/// The top of a relative listing or an archive is the empty path
+fn display_root(root: &Path) -> std::path::Display<'_> {
+    if root.as_os_str().is_empty() {
+        Path::new(".").display()
+    } else {
+        root.display()
+    }
+}
+
 fn run(args: &Args) -> Result<bool> {
     match &args.command {
         Some(Command::Traverse {
             directory,
+            archive,
             extensions,
             max_depth,
             roots_only,
-        }) => run_traverse(
-            directory,
-            extensions.as_ref(),
-            *max_depth,
-            *roots_only,
+            ignore_files,
+            threads,
+        }) => {
+            let config = load_config(args, directory)?;
+            let options = traversal_options(
+                extensions.as_ref(),
+                *max_depth,
+                *ignore_files,
+                traverse_threads(*threads, *roots_only),
+            );
+            let run = if *archive {
+                run_traverse_archive
+            } else {
+                run_traverse
+            };
+            run(
+                &config,
+                directory,
+                &options,
+                *roots_only,
+                args.json,
+                args.check,
+            )
+        }
+
+        Some(Command::Files {
+            files,
+            batch,
+            listing,
+            git,
+            cache,
+        }) => {
+            let config = load_config(args, Path::new("."))?;
+            let cache = cache_enabled(*cache, std::env::var_os("ROOTDETECT_CACHE").as_deref())?;
+            let marker_cache = match default_cache_dir() {
+                Some(dir) if cache && !*listing => MarkerCache::open(&dir, &config),
+                _ => MarkerCache::new(),
+            };
+            run_files(&config, files, *batch, *listing, *git, &marker_cache, args)
+        }
+
+        Some(Command::Affected { paths, diff }) => run_affected(
+            &load_config(args, Path::new("."))?,
+            paths,
+            diff.as_deref(),
+            args.json,
+        ),
+
+        Some(Command::Ancestors { files, batch }) => run_ancestors(
+            &load_config(args, Path::new("."))?,
+            files,
+            *batch,
             args.json,
             args.check,
         ),
 
-        Some(Command::Files { files, batch }) => run_files(files, *batch, args.json, args.check),
+        Some(Command::Workspace { directory }) => {
+            run_workspace(&load_config(args, directory)?, directory, args.json)
+        }
+
+        Some(Command::Exec {
+            directory,
+            command,
+            kind,
+            jobs,
+            max_depth,
+            ignore_files,
+            include_orphanages,
+        }) => {
+            let config = load_config(args, directory)?;
+            let options = traversal_options(None, *max_depth, *ignore_files, 0);
+            let roots = exec_roots(directory, &config, &options, kind, *include_orphanages);
+            run_exec(&roots, command, *jobs, args.json)
+        }
+
+        Some(Command::Cache { action }) => run_cache(action, args.json),
 
         // Default: if a single path is provided and it's a directory, traverse it
         // Otherwise, treat arguments as files (backwards compatibility)
@@ -263,30 +1080,39 @@ fn main() -> ExitCode {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use project_root_detector::Marker;
 
     #[test]
     fn test_file_result_serialization() {
         let result = FileResult {
             file: PathBuf::from("/test/file.rs"),
             root: Some(PathBuf::from("/test")),
+            markers: vec!["Cargo.toml".to_string()],
+            case: Some(DetectionCase::Marker),
+            kind: Some(ProjectKind::Rust),
+            decision: None,
+            workspace: None,
+            package: None,
+            build_package: None,
             excluded: false,
         };
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("file"));
         assert!(json.contains("root"));
+        assert!(json.contains(r#""markers":["Cargo.toml"]"#));
+        assert!(json.contains(r#""case":"marker""#));
+        assert!(json.contains(r#""kind":"rust""#));
         assert!(!json.contains("excluded")); // excluded: false should be skipped
     }
 
     #[test]
     fn test_file_result_excluded_serialization() {
-        let result = FileResult {
-            file: PathBuf::from("/test/node_modules/pkg/index.js"),
-            root: None,
-            excluded: true,
-        };
+        let result = FileResult::new(PathBuf::from("/test/node_modules/pkg/index.js"), None);
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("excluded"));
         assert!(!json.contains(r#""root""#)); // None should be skipped
+        assert!(!json.contains("markers"));
+        assert!(!json.contains("kind"));
     }
 
     #[test]
@@ -301,6 +1127,217 @@ mod tests {
         assert!(json.contains("/project1"));
     }
 
+    /// Memory filesystem counting directory reads
+    #[derive(Debug)]
+    struct CountingFs {
+        inner: project_root_detector::MemoryFs,
+        reads: std::sync::Arc<AtomicUsize>,
+    }
+
+    impl project_root_detector::FileSystem for CountingFs {
+        fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.inner.read_dir(dir)
+        }
+        fn is_dir(&self, path: &Path) -> bool {
+            self.inner.is_dir(path)
+        }
+        fn is_file(&self, path: &Path) -> bool {
+            self.inner.is_file(path)
+        }
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            self.inner.canonicalize(path)
+        }
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.inner.read_to_string(path)
+        }
+    }
+
+    /// Output recording how many directories had been read at each flush
+    struct ReadsAtWrite {
+        reads: std::sync::Arc<AtomicUsize>,
+        seen: Vec<usize>,
+    }
+
+    impl Write for ReadsAtWrite {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.seen.push(self.reads.load(Ordering::SeqCst));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_traverse_streams_by_default() {
+        let mut fs = project_root_detector::MemoryFs::new();
+        for name in ["a", "b", "c", "d"] {
+            fs.add_file(format!("/code/{name}/Cargo.toml"), "");
+            fs.add_file(format!("/code/{name}/src/lib.rs"), "");
+        }
+        let reads = std::sync::Arc::new(AtomicUsize::new(0));
+        let config = Config::default().with_filesystem(CountingFs {
+            inner: fs,
+            reads: reads.clone(),
+        });
+
+        let args = Args::try_parse_from(["prd", "traverse", "-e", "rs", "/code"]).unwrap();
+        let Some(Command::Traverse {
+            threads,
+            extensions,
+            ..
+        }) = args.command
+        else {
+            unreachable!();
+        };
+        let options = traversal_options(
+            extensions.as_ref(),
+            None,
+            false,
+            traverse_threads(threads, false),
+        );
+
+        let mut out = ReadsAtWrite {
+            reads: reads.clone(),
+            seen: Vec::new(),
+        };
+        let results = traversal_results(Path::new("/code"), &config, &options);
+        write_traversal(results, false, &mut out).unwrap();
+        let total = reads.load(Ordering::SeqCst);
+        assert_eq!(out.seen.len(), 4);
+        // The first result is written long before the walk reads its last directory
+        assert!(out.seen[0] < total);
+    }
+
+    #[test]
+    fn test_marker_flags() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let weight = |argv: &[&str]| {
+            let config = load_config(&Args::try_parse_from(argv).unwrap(), temp.path()).unwrap();
+            config.markers.get("CMakeLists.txt").unwrap().weight
+        };
+        assert_eq!(weight(&["prd", "files", "a.rs"]), Marker::DEFAULT_WEIGHT);
+        assert_eq!(
+            weight(&["prd", "files", "--weak-defaults", "a.rs"]),
+            Marker::WEAK_WEIGHT
+        );
+
+        let predicates = |argv: &[&str]| {
+            let config = load_config(&Args::try_parse_from(argv).unwrap(), temp.path()).unwrap();
+            config.markers.get("package.json").unwrap().predicates.len()
+        };
+        assert_eq!(predicates(&["prd", "files", "a.rs"]), 0);
+        assert_eq!(
+            predicates(&["prd", "files", "--builtin-predicates", "a.rs"]),
+            1
+        );
+
+        let dotnet = |argv: &[&str]| {
+            let config = load_config(&Args::try_parse_from(argv).unwrap(), temp.path()).unwrap();
+            let weight = |name| config.markers.get(name).map(|m: &Marker| m.weight);
+            (weight("*.csproj"), weight("global.json"))
+        };
+        assert_eq!(dotnet(&["prd", "files", "a.rs"]), (None, None));
+        assert_eq!(
+            dotnet(&["prd", "files", "--dotnet", "a.rs"]),
+            (Some(Marker::DEFAULT_WEIGHT), Some(Marker::WEAK_WEIGHT))
+        );
+    }
+
+    #[test]
+    fn test_ancestors_of_relative_path() {
+        // Tests run from the crate root, which holds Cargo.toml
+        let result = ancestors_result(PathBuf::from("src/main.rs"), &Config::default());
+        assert_eq!(result.roots[0].root, PathBuf::from("."));
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains(r#""root":".""#));
+        assert!(!json.contains(r#""root":"""#));
+    }
+
+    #[test]
+    fn test_diff_revisions() {
+        let dir = Path::new(".");
+        let pair = |from: &str, to: &str| (from.to_string(), to.to_string());
+        assert_eq!(diff_revisions(dir, "main").unwrap(), pair("main", "HEAD"));
+        assert_eq!(diff_revisions(dir, "main..").unwrap(), pair("main", "HEAD"));
+        assert_eq!(diff_revisions(dir, "v1..v2").unwrap(), pair("v1", "v2"));
+
+        // Symmetric ranges need the merge base, never `main` and `.HEAD`
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(diff_revisions(temp.path(), "main...HEAD").is_err());
+    }
+
+    #[test]
+    fn test_cache_flag_from_environment() {
+        let env = |value: &'static str| Some(OsStr::new(value));
+        assert!(!cache_enabled(false, None).unwrap());
+        assert!(cache_enabled(false, env("1")).unwrap());
+        assert!(cache_enabled(false, env("Yes")).unwrap());
+        assert!(!cache_enabled(false, env("off")).unwrap());
+        assert!(cache_enabled(true, env("off")).unwrap());
+        assert!(cache_enabled(false, env("maybe")).is_err());
+
+        // Other commands do not take the flag
+        assert!(Args::try_parse_from(["prd", "files", "--cache", "a.rs"]).is_ok());
+        assert!(Args::try_parse_from(["prd", "traverse", "--cache", "."]).is_err());
+    }
+
+    #[test]
+    fn test_exec_skips_orphanages_by_default() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path();
+        for (path, contents) in [
+            ("app/Cargo.toml", "[package]"),
+            ("app/src/main.rs", ""),
+            ("scripts/tool.py", ""),
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let config = Config::default();
+        let options = traversal_options(None, None, false, 0);
+        let roots = |include_orphanages| -> Vec<PathBuf> {
+            exec_roots(dir, &config, &options, &[], include_orphanages)
+                .into_iter()
+                .map(|(root, _)| root)
+                .collect()
+        };
+        assert_eq!(roots(false), vec![dir.join("app")]);
+        // The orphanage groups the loose script with its sibling project
+        assert_eq!(roots(true), vec![dir.to_path_buf(), dir.join("app")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_collects_exit_statuses() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let roots: Vec<(PathBuf, ProjectKind)> = ["b", "a", "c"]
+            .iter()
+            .map(|name| (temp.path().join(name), ProjectKind::Unknown))
+            .collect();
+        for (root, _) in &roots {
+            std::fs::create_dir(root).unwrap();
+        }
+        std::fs::write(temp.path().join("a/ok"), "").unwrap();
+
+        let command = ["sh", "-c", "test -f ok"].map(String::from);
+        let results = exec_in_roots(&roots, &command, 2, true);
+        let statuses: Vec<(PathBuf, Option<i32>)> =
+            results.iter().map(|r| (r.root.clone(), r.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (temp.path().join("a"), Some(0)),
+                (temp.path().join("b"), Some(1)),
+                (temp.path().join("c"), Some(1)),
+            ]
+        );
+        assert!(results[0].success && !results[1].success);
+    }
+
     #[test]
     fn verify_cli() {
         use clap::CommandFactory;
@@ -0,0 +1,382 @@
+//! Weighted project markers.
+//!
+//! Every marker carries a weight. Walking up from a file, the closest marker
+//! directory wins unless an enclosing directory holds a strictly heavier
+//! marker. All default markers weigh the same; with
+//! [`crate::Config::with_weak_defaults`] a stray `CMakeLists.txt` in a
+//! subdirectory defers to the repository's `.git`:
+//!
+//! ```text
+//! repo/
+//! ├── .git                  ← weight 10
+//! └── tools/
+//!     ├── CMakeLists.txt    ← weight 5, outweighed
+//!     └── gen.cpp           → root: repo/ (reason: outweighed)
+//! ```
+//!
+//! A marker can also carry [`MarkerPredicate`]s on the file's contents, so a
+//! `package.json` without a `name` (tooling config) does not count as a root.
+//! [`crate::Config::with_builtin_predicates`] adds such checks to the default
+//! markers.
+//!
+//! Marker names may be globs matched against the entries of a directory
+//! (`*.sln`, `*.csproj`); other names are paths relative to the directory.
+
+use crate::glob::{is_glob, Glob};
+use crate::FileSystem;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Default markers that commonly appear below the real project root
+/// (per-directory `CMake` scripts), made weak by
+/// [`crate::Config::with_weak_defaults`]
+pub const WEAK_DEFAULT_MARKERS: &[&str] = &["CMakeLists.txt"];
+
+/// Condition on the contents of a marker file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerPredicate {
+    /// The file contains any of the strings
+    Contains {
+        /// Candidate substrings
+        any: Vec<String>,
+        /// Compare ignoring ASCII case
+        ignore_case: bool,
+    },
+    /// The file is TOML defining any of the dotted keys (e.g. `tool.poetry`)
+    TomlKeys(Vec<String>),
+    /// The file is a JSON object with any of the top-level keys
+    JsonKeys(Vec<String>),
+}
+
+impl MarkerPredicate {
+    /// Whether `contents` satisfies the predicate
+    #[must_use]
+    pub fn holds(&self, contents: &str) -> bool {
+        match self {
+            Self::Contains { any, ignore_case } => {
+                if *ignore_case {
+                    let lower = contents.to_ascii_lowercase();
+                    any.iter().any(|s| lower.contains(&s.to_ascii_lowercase()))
+                } else {
+                    any.iter().any(|s| contents.contains(s.as_str()))
+                }
+            }
+            Self::TomlKeys(keys) => contents.parse::<toml::Table>().is_ok_and(|table| {
+                keys.iter().any(|key| {
+                    let mut parts = key.split('.');
+                    let first = parts.next().and_then(|p| table.get(p));
+                    parts
+                        .try_fold(first, |value, part| Some(value?.get(part)))
+                        .flatten()
+                        .is_some()
+                })
+            }),
+            Self::JsonKeys(keys) => serde_json::from_str::<serde_json::Value>(contents)
+                .is_ok_and(|value| keys.iter().any(|key| value.get(key).is_some())),
+        }
+    }
+}
+
+/// Built-in content checks for default markers that often appear in
+/// non-root directories, added by [`crate::Config::with_builtin_predicates`]
+fn default_predicate(name: &str) -> Option<MarkerPredicate> {
+    let strings = |items: &[&str]| items.iter().copied().map(String::from).collect();
+    let predicate = match name {
+        "CMakeLists.txt" => MarkerPredicate::Contains {
+            any: strings(&["project(", "project ("]),
+            ignore_case: true,
+        },
+        "pyproject.toml" => {
+            MarkerPredicate::TomlKeys(strings(&["project", "tool.poetry", "tool.uv.workspace"]))
+        }
+        "package.json" => MarkerPredicate::JsonKeys(strings(&["name", "workspaces"])),
+        "setup.py" => MarkerPredicate::Contains {
+            any: strings(&["setup("]),
+            ignore_case: false,
+        },
+        "setup.cfg" => MarkerPredicate::Contains {
+            any: strings(&["[metadata]", "[options]"]),
+            ignore_case: false,
+        },
+        "go.mod" => MarkerPredicate::Contains {
+            any: strings(&["module"]),
+            ignore_case: false,
+        },
+        _ => return None,
+    };
+    Some(predicate)
+}
+
+/// A project marker filename with its weight.
+///
+/// Markers are identified by name: a marker set holds at most one weight per
+/// name, and can be queried with a plain `&str`.
+#[derive(Debug, Clone)]
+pub struct Marker {
+    /// Filename or name glob that marks a project root
+    name: String,
+    /// Compiled name glob (exact and case-folded), if `name` is a glob
+    glob: Option<(Glob, Glob)>,
+    /// How strongly the marker indicates a root; heavier markers displace
+    /// lighter ones found closer to the file
+    pub weight: u32,
+    /// Conditions the file's contents must all meet (none: existence is enough)
+    pub predicates: Vec<MarkerPredicate>,
+}
+
+impl Marker {
+    /// Weight of ordinary markers
+    pub const DEFAULT_WEIGHT: u32 = 10;
+    /// Weight of [`WEAK_DEFAULT_MARKERS`]
+    pub const WEAK_WEIGHT: u32 = 5;
+
+    /// Create a marker with [`Marker::DEFAULT_WEIGHT`]
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let glob = is_glob(&name).then(|| (Glob::new(&name, false), Glob::new(&name, true)));
+        Self {
+            name,
+            glob,
+            weight: Self::DEFAULT_WEIGHT,
+            predicates: Vec::new(),
+        }
+    }
+
+    /// Create a marker with [`Marker::WEAK_WEIGHT`]
+    #[must_use]
+    pub fn weak(name: impl Into<String>) -> Self {
+        Self::new(name).with_weight(Self::WEAK_WEIGHT)
+    }
+
+    /// Set the marker weight
+    #[must_use]
+    pub const fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Filename or name glob of the marker
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the marker is a name glob rather than a fixed name
+    #[must_use]
+    pub const fn is_glob(&self) -> bool {
+        self.glob.is_some()
+    }
+
+    /// Whether a directory entry called `name` is this marker's file
+    pub(crate) fn matches_name(&self, name: &str, case_insensitive: bool) -> bool {
+        match &self.glob {
+            Some((exact, folded)) => {
+                let glob = if case_insensitive { folded } else { exact };
+                glob.matches_components(&[name])
+            }
+            None if case_insensitive => self.name.to_lowercase() == name.to_lowercase(),
+            None => self.name == name,
+        }
+    }
+
+    /// Whether the marker is present in `dir` and its contents match.
+    ///
+    /// `entries` lists the directory's entry names; it is only consulted for
+    /// globs and case-insensitive lookups.
+    pub(crate) fn is_present_in(
+        &self,
+        fs: &dyn FileSystem,
+        dir: &Path,
+        entries: &[String],
+        case_insensitive: bool,
+    ) -> bool {
+        if let Some((exact, folded)) = &self.glob {
+            let glob = if case_insensitive { folded } else { exact };
+            return entries
+                .iter()
+                .filter(|entry| glob.matches_components(&[entry]))
+                .any(|entry| self.contents_match(fs, &dir.join(entry)));
+        }
+
+        let path = dir.join(&self.name);
+        if fs.exists(&path) {
+            return self.contents_match(fs, &path);
+        }
+        case_insensitive && {
+            let lower = self.name.to_lowercase();
+            entries
+                .iter()
+                .find(|entry| entry.to_lowercase() == lower)
+                .is_some_and(|entry| self.contents_match(fs, &dir.join(entry)))
+        }
+    }
+
+    /// Add a condition on the file's contents
+    #[must_use]
+    pub fn with_predicate(mut self, predicate: MarkerPredicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Whether the marker file meets the predicates (unreadable files never do)
+    fn contents_match(&self, fs: &dyn FileSystem, path: &Path) -> bool {
+        self.predicates.is_empty()
+            || fs
+                .read_to_string(path)
+                .is_ok_and(|contents| self.predicates.iter().all(|p| p.holds(&contents)))
+    }
+
+    /// Add the built-in content predicate for the marker's name, unless it
+    /// has none or the marker already carries predicates of its own
+    pub(crate) fn with_default_predicate(self) -> Self {
+        match default_predicate(&self.name) {
+            Some(predicate) if self.predicates.is_empty() => self.with_predicate(predicate),
+            _ => self,
+        }
+    }
+}
+
+impl From<&str> for Marker {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Marker {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl PartialEq for Marker {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Marker {}
+
+impl Hash for Marker {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl Borrow<str> for Marker {
+    fn borrow(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Why a marker directory was chosen as the root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionReason {
+    /// Closest marker directory with no heavier marker above it
+    Innermost,
+    /// Heavier than the markers of a closer directory, which was passed over
+    Outweighed,
+    /// Farthest marker directory ([`crate::RootStrategy::Outermost`])
+    Outermost,
+    /// Closest version control root ([`crate::RootStrategy::Vcs`])
+    Vcs,
+    /// Best-ranked marker of [`crate::RootStrategy::Priority`]
+    Priority,
+    /// Closest Bazel, Buck or Pants repository root ([`crate::RootStrategy::BuildSystem`])
+    BuildSystem,
+    /// One of several enclosing roots listed by [`crate::find_root_chain`]
+    Enclosing,
+}
+
+/// The marker that decided a root, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkerDecision {
+    /// Name of the winning marker
+    pub marker: String,
+    /// Weight of the winning marker
+    pub weight: u32,
+    /// Why its directory was chosen
+    pub reason: DecisionReason,
+    /// Closer marker directory that was passed over ([`DecisionReason::Outweighed`] only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub displaced: Option<PathBuf>,
+}
+
+impl MarkerDecision {
+    /// Decision for the heaviest of `found` (ties go to the first by name)
+    pub(crate) fn heaviest(found: &[&Marker], reason: DecisionReason) -> Option<Self> {
+        let winner = found
+            .iter()
+            .copied()
+            .reduce(|best, m| if m.weight > best.weight { m } else { best })?;
+        Some(Self {
+            marker: winner.name().to_string(),
+            weight: winner.weight,
+            reason,
+            displaced: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_markers_are_identified_by_name() {
+        let mut markers: HashSet<Marker> = ["Cargo.toml", "CMakeLists.txt"]
+            .into_iter()
+            .map(Marker::new)
+            .collect();
+        assert!(markers.contains("Cargo.toml"));
+        assert_eq!(
+            markers.get("CMakeLists.txt").unwrap().weight,
+            Marker::DEFAULT_WEIGHT
+        );
+
+        markers.replace(Marker::new("CMakeLists.txt").with_weight(20));
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers.get("CMakeLists.txt").unwrap().weight, 20);
+    }
+
+    #[test]
+    fn test_default_predicates() {
+        let holds = |name: &str, contents: &str| {
+            Marker::new(name)
+                .with_default_predicate()
+                .predicates
+                .iter()
+                .all(|p| p.holds(contents))
+        };
+
+        assert!(holds(
+            "CMakeLists.txt",
+            "cmake_minimum_required(VERSION 3.20)\nPROJECT (demo)\n"
+        ));
+        assert!(!holds("CMakeLists.txt", "add_library(util util.cpp)\n"));
+        assert!(holds("pyproject.toml", "[project]\nname = \"demo\"\n"));
+        assert!(holds("pyproject.toml", "[tool.poetry]\nname = \"demo\"\n"));
+        assert!(!holds("pyproject.toml", "[tool.ruff]\nline-length = 100\n"));
+        assert!(holds("package.json", r#"{"name": "demo"}"#));
+        assert!(!holds("package.json", r#"{"prettier": {}}"#));
+        assert!(!holds("package.json", "not json"));
+        assert!(holds(".git", ""));
+    }
+
+    #[test]
+    fn test_heaviest_marker_wins_ties_by_order() {
+        let git = Marker::new(".git");
+        let cmake = Marker::weak("CMakeLists.txt");
+        let cargo = Marker::new("Cargo.toml");
+
+        let decision =
+            MarkerDecision::heaviest(&[&cmake, &cargo, &git], DecisionReason::Innermost).unwrap();
+        assert_eq!(decision.marker, "Cargo.toml");
+        assert_eq!(decision.weight, Marker::DEFAULT_WEIGHT);
+        assert!(MarkerDecision::heaviest(&[], DecisionReason::Innermost).is_none());
+    }
+}
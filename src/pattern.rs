@@ -0,0 +1,414 @@
+//! Exclusion patterns.
+//!
+//! Exclusions can be plain directory names (`node_modules`), name globs
+//! (`*.egg-info`) or path globs (`docs/**/generated`). Path globs match the
+//! path of a directory relative to the set's anchor: the directory of the
+//! config file that listed them, or else the traversal root. A set without an
+//! anchor matches them against any trailing part of the path, as a gitignore
+//! without a base directory would, unless they start with `/`. Globs are
+//! compiled once, when inserted; plain names stay a hash lookup.
+
+use crate::glob::{is_glob, Glob};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Set of exclusion patterns with precompiled matchers
+#[derive(Debug, Clone, Default)]
+pub struct PatternSet {
+    patterns: HashSet<String>,
+    names: HashSet<String>,
+    folded_names: HashSet<String>,
+    name_globs: Vec<CompiledGlob>,
+    path_globs: Vec<CompiledGlob>,
+    anchors: Vec<Anchor>,
+}
+
+/// A spelling of the directory that path globs are relative to
+#[derive(Debug, Clone)]
+struct Anchor {
+    /// Components of the spelling
+    prefix: Vec<String>,
+    /// Components between the anchor directory and `prefix`, when `prefix`
+    /// spells a directory below it
+    lead: Vec<String>,
+}
+
+impl Anchor {
+    fn new(dir: &Path) -> Self {
+        Self {
+            prefix: path_components(dir).into_iter().map(String::from).collect(),
+            lead: Vec::new(),
+        }
+    }
+
+    /// Components of `path` relative to the anchor directory, if below it
+    fn relative<'a, S: AsRef<str>>(
+        &'a self,
+        path: &'a [S],
+        case_insensitive: bool,
+    ) -> Option<Vec<&'a str>> {
+        let rest = path.get(self.prefix.len()..)?;
+        let same = |(a, b): (&String, &S)| {
+            if case_insensitive {
+                a.eq_ignore_ascii_case(b.as_ref())
+            } else {
+                a == b.as_ref()
+            }
+        };
+        if !self.prefix.iter().zip(path).all(same) {
+            return None;
+        }
+        let relative: Vec<&str> = self
+            .lead
+            .iter()
+            .map(String::as_str)
+            .chain(rest.iter().map(AsRef::as_ref))
+            .collect();
+        (!relative.is_empty()).then_some(relative)
+    }
+}
+
+/// A glob compiled for case-sensitive and case-insensitive matching
+#[derive(Debug, Clone)]
+struct CompiledGlob {
+    pattern: String,
+    exact: Glob,
+    folded: Glob,
+}
+
+impl CompiledGlob {
+    fn new(pattern: &str, compiled: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            exact: Glob::new(compiled, false),
+            folded: Glob::new(compiled, true),
+        }
+    }
+
+    /// Whether the pattern starts with `/`, so it never floats to a subpath
+    fn is_rooted(&self) -> bool {
+        self.pattern.starts_with('/')
+    }
+
+    fn matches<S: AsRef<str>>(&self, components: &[S], case_insensitive: bool) -> bool {
+        if case_insensitive {
+            self.folded.matches_components(components)
+        } else {
+            self.exact.matches_components(components)
+        }
+    }
+}
+
+impl PatternSet {
+    /// Create an empty set
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern; returns `false` if it was already present
+    pub fn insert(&mut self, pattern: impl Into<String>) -> bool {
+        let pattern = pattern.into();
+        if self.patterns.contains(&pattern) {
+            return false;
+        }
+
+        if pattern.contains('/') {
+            let relative = pattern.trim_start_matches('/');
+            self.path_globs.push(CompiledGlob::new(&pattern, relative));
+        } else if is_glob(&pattern) {
+            self.name_globs.push(CompiledGlob::new(&pattern, &pattern));
+        } else {
+            self.names.insert(pattern.clone());
+            self.folded_names.insert(pattern.to_lowercase());
+        }
+        self.patterns.insert(pattern)
+    }
+
+    /// Remove a pattern; returns `false` if it was not present
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        if !self.patterns.remove(pattern) {
+            return false;
+        }
+        self.names.remove(pattern);
+        self.folded_names = self.names.iter().map(|n| n.to_lowercase()).collect();
+        self.name_globs.retain(|g| g.pattern != pattern);
+        self.path_globs.retain(|g| g.pattern != pattern);
+        true
+    }
+
+    /// Whether the exact pattern is in the set
+    #[must_use]
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.patterns.contains(pattern)
+    }
+
+    /// Number of patterns
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Whether the set has no patterns
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Iterate over the patterns, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().map(String::as_str)
+    }
+
+    /// Keep only the patterns for which `keep` returns `true`
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let dropped: Vec<String> = self
+            .patterns
+            .iter()
+            .filter(|pattern| !keep(pattern))
+            .cloned()
+            .collect();
+        for pattern in dropped {
+            self.remove(&pattern);
+        }
+    }
+
+    /// Make path globs relative to `dir` (added to any existing anchors).
+    ///
+    /// `dir` should be spelled like the paths being matched: canonical for
+    /// [`crate::is_excluded`], as walked for traversals.
+    pub fn anchor_at(&mut self, dir: &Path) {
+        self.anchors.push(Anchor::new(dir));
+    }
+
+    /// Whether path globs are relative to an anchor directory
+    #[must_use]
+    pub fn is_anchored(&self) -> bool {
+        !self.anchors.is_empty()
+    }
+
+    /// Patterns and anchors, sorted: equal for sets that match alike
+    pub(crate) fn fingerprint(&self) -> Vec<String> {
+        let mut parts: Vec<String> = self
+            .patterns
+            .iter()
+            .cloned()
+            .chain(
+                self.anchors
+                    .iter()
+                    .map(|a| format!("{}\0{}", a.prefix.join("/"), a.lead.join("/"))),
+            )
+            .collect();
+        parts.sort();
+        parts
+    }
+
+    /// Whether the set holds any path glob
+    #[must_use]
+    pub fn has_path_globs(&self) -> bool {
+        !self.path_globs.is_empty()
+    }
+
+    /// Also accept `alias` as a spelling of `canonical`, for the anchors that
+    /// `canonical` lies below (a relative traversal root, say)
+    pub fn add_alias(&mut self, alias: &Path, canonical: &Path) {
+        let canonical = path_components(canonical);
+        let prefix = Anchor::new(alias).prefix;
+        let aliases: Vec<Anchor> = self
+            .anchors
+            .iter()
+            .filter(|anchor| {
+                anchor.prefix.len() <= canonical.len()
+                    && anchor.prefix.iter().zip(&canonical).all(|(a, b)| a == b)
+            })
+            .map(|anchor| Anchor {
+                prefix: prefix.clone(),
+                lead: anchor
+                    .lead
+                    .iter()
+                    .map(String::as_str)
+                    .chain(canonical[anchor.prefix.len()..].iter().copied())
+                    .map(String::from)
+                    .collect(),
+            })
+            .collect();
+        self.anchors.extend(aliases);
+    }
+
+    /// Whether a directory name matches a plain name or name glob
+    #[must_use]
+    pub fn matches_name(&self, name: &str, case_insensitive: bool) -> bool {
+        let named = if case_insensitive {
+            self.folded_names.contains(&name.to_lowercase())
+        } else {
+            self.names.contains(name)
+        };
+        named
+            || self
+                .name_globs
+                .iter()
+                .any(|g| g.matches(&[name], case_insensitive))
+    }
+
+    /// Whether the directory made of `components` matches: by its last
+    /// component's name, or by a path glob over its path relative to an anchor
+    #[must_use]
+    pub fn matches_components<S: AsRef<str>>(
+        &self,
+        components: &[S],
+        case_insensitive: bool,
+    ) -> bool {
+        components
+            .last()
+            .is_some_and(|name| self.matches_name(name.as_ref(), case_insensitive))
+            || self.matches_path(components, case_insensitive)
+    }
+
+    fn matches_path<S: AsRef<str>>(&self, components: &[S], case_insensitive: bool) -> bool {
+        if self.path_globs.is_empty() {
+            return false;
+        }
+        if self.anchors.is_empty() {
+            return self.path_globs.iter().any(|g| {
+                if g.is_rooted() {
+                    g.matches(components, case_insensitive)
+                } else {
+                    (0..components.len()).any(|i| g.matches(&components[i..], case_insensitive))
+                }
+            });
+        }
+        self.anchors
+            .iter()
+            .filter_map(|anchor| anchor.relative(components, case_insensitive))
+            .any(|relative| {
+                self.path_globs
+                    .iter()
+                    .any(|g| g.matches(&relative, case_insensitive))
+            })
+    }
+
+    /// Whether `dir` matches, see [`PatternSet::matches_components`]
+    #[must_use]
+    pub fn matches_dir(&self, dir: &Path, case_insensitive: bool) -> bool {
+        let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        self.matches_name(name, case_insensitive)
+            || (self.has_path_globs() && self.matches_path(&path_components(dir), case_insensitive))
+    }
+}
+
+/// Normal components of a path, as strings
+pub fn path_components(path: &Path) -> Vec<&str> {
+    path.components()
+        .filter(|c| matches!(c, std::path::Component::Normal(_)))
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect()
+}
+
+impl<S: Into<String>> FromIterator<S> for PatternSet {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<S: Into<String>, H> From<HashSet<S, H>> for PatternSet {
+    /// Patterns of a plain set, as `Config::exclusions` used to be
+    fn from(patterns: HashSet<S, H>) -> Self {
+        patterns.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a PatternSet {
+    type Item = &'a str;
+    type IntoIter =
+        std::iter::Map<std::collections::hash_set::Iter<'a, String>, fn(&String) -> &str>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.patterns.iter().map(String::as_str)
+    }
+}
+
+impl<S: Into<String>> Extend<S> for PatternSet {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for pattern in iter {
+            self.insert(pattern);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_and_globs() {
+        let set: PatternSet = ["node_modules", "*.egg-info", "docs/**/generated"]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 3);
+
+        assert!(set.matches_name("node_modules", false));
+        assert!(set.matches_name("mypkg.egg-info", false));
+        assert!(!set.matches_name("generated", false));
+        assert!(!set.matches_name("Node_Modules", false));
+        assert!(set.matches_name("Node_Modules", true));
+
+        // Without an anchor, path globs match any trailing part of the path
+        assert!(set.matches_dir(Path::new("docs/api/v1/generated"), false));
+        assert!(set.matches_dir(Path::new("/repo/docs/generated"), false));
+        assert!(!set.matches_dir(Path::new("/repo/docs/generated/html"), false));
+        assert!(!set.matches_dir(Path::new("/"), false));
+
+        // ... unless they start with `/`
+        let mut rooted = PatternSet::new();
+        rooted.insert("/scratch/*");
+        assert!(rooted.matches_dir(Path::new("scratch/tmp"), false));
+        assert!(!rooted.matches_dir(Path::new("/repo/scratch/tmp"), false));
+    }
+
+    #[test]
+    fn test_path_globs_are_anchored() {
+        let mut set = PatternSet::new();
+        set.insert("docs/**/generated");
+        set.anchor_at(Path::new("/home/me/repo"));
+        assert!(set.is_anchored());
+
+        assert!(set.matches_dir(Path::new("/home/me/repo/docs/api/v1/generated"), false));
+        assert!(set.matches_dir(Path::new("/home/me/repo/docs/generated"), false));
+        assert!(!set.matches_dir(Path::new("/home/me/repo/src/generated"), false));
+        assert!(!set.matches_dir(Path::new("/home/me/repo/docs/generated/html"), false));
+        assert!(!set.matches_dir(Path::new("/home/me/repo/vendor/docs/generated"), false));
+        // Outside the anchor nothing matches
+        assert!(!set.matches_dir(Path::new("/home/docs/generated"), false));
+        assert!(!set.matches_dir(Path::new("/srv/docs/generated"), false));
+
+        // A relative spelling of a directory below the anchor
+        set.add_alias(Path::new("api"), Path::new("/home/me/repo/docs/api"));
+        assert!(set.matches_dir(Path::new("api/v1/generated"), false));
+        assert!(!set.matches_dir(Path::new("v1/generated"), false));
+        set.add_alias(Path::new(""), Path::new("/elsewhere"));
+        assert!(!set.matches_dir(Path::new("docs/generated"), false));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set: PatternSet = ["vendor", "*.tmp", "a/b"].into_iter().collect();
+        assert!(set.remove("vendor"));
+        assert!(set.remove("*.tmp"));
+        assert!(set.remove("a/b"));
+        assert!(!set.remove("vendor"));
+        assert!(set.is_empty());
+        assert!(!set.matches_name("vendor", true));
+        assert!(!set.matches_name("x.tmp", false));
+        assert!(!set.matches_dir(Path::new("a/b"), false));
+
+        let mut set: PatternSet = ["vendor", "*.tmp", "a/b"].into_iter().collect();
+        set.retain(|pattern| !pattern.contains('*'));
+        let mut left: Vec<&str> = (&set).into_iter().collect();
+        left.sort_unstable();
+        assert_eq!(left, vec!["a/b", "vendor"]);
+    }
+}
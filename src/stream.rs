@@ -0,0 +1,245 @@
+//! Streaming traversal.
+//!
+//! [`TraversalIter`] walks the tree depth-first in sorted order and yields
+//! each file's result as soon as it is decidable:
+//!
+//! - excluded files and files under a marker directory right away;
+//! - orphans once their orphanage can no longer move up. Seeing more source
+//!   directories can only raise an orphanage, and the decision to stop at a
+//!   directory only depends on its parent's subtree and on the files directly
+//!   in the parent's ancestors (read before the walk descends). So a result
+//!   is final once the orphanage's parent directory has been fully walked.
+//!
+//! Results match [`crate::traverse_and_detect`], in walk order instead of
+//! sorted order.
+
+use crate::walk::{scan_dir, DirJob};
+use crate::{
+    find_marker_root, find_orphanage, is_excluded, Config, DetectionCase, ExclusionCache,
+    MarkerCache, RootInfo, TraversalOptions, TraversalResult,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+enum Step {
+    /// Read a directory
+    Enter(DirJob),
+    /// Everything below the directory has been read
+    Leave(PathBuf),
+}
+
+/// Lazy traversal yielding [`TraversalResult`]s as their roots become known.
+///
+/// Created by [`traverse_iter`].
+pub struct TraversalIter<'a> {
+    config: Cow<'a, Config>,
+    options: &'a TraversalOptions,
+    start: PathBuf,
+    cache: ExclusionCache,
+    marker_cache: MarkerCache,
+    steps: Vec<Step>,
+    /// Directories containing valid source files seen so far
+    source_dirs: HashSet<PathBuf>,
+    /// Orphans waiting for a directory to be left, keyed by that directory
+    /// (the parent of their current orphanage)
+    pending: HashMap<PathBuf, Vec<PathBuf>>,
+    ready: VecDeque<TraversalResult>,
+}
+
+/// Traverse a directory tree lazily, see [`TraversalIter`].
+///
+/// Unlike [`crate::traverse_and_detect`], files are not collected up front:
+/// memory holds the directory stack, the set of source directories and the
+/// orphans whose root is not decided yet. The walk is single-threaded and
+/// ignores [`TraversalOptions::threads`].
+#[must_use]
+pub fn traverse_iter<'a>(
+    start_path: &Path,
+    config: &'a Config,
+    options: &'a TraversalOptions,
+) -> TraversalIter<'a> {
+    let config = config.anchored_at(start_path);
+    TraversalIter {
+        steps: vec![Step::Enter(DirJob::start(start_path, &config, options))],
+        config,
+        options,
+        start: start_path.to_path_buf(),
+        cache: ExclusionCache::new(),
+        marker_cache: MarkerCache::new(),
+        source_dirs: HashSet::new(),
+        pending: HashMap::new(),
+        ready: VecDeque::new(),
+    }
+}
+
+impl TraversalIter<'_> {
+    fn enter(&mut self, job: DirJob) {
+        let dir = job.dir.clone();
+        let mut files = Vec::new();
+        let mut subdirs = scan_dir(job, &self.config, self.options, &mut files);
+        files.sort();
+        subdirs.sort_by(|a, b| b.dir.cmp(&a.dir));
+
+        self.steps.push(Step::Leave(dir));
+        self.steps.extend(subdirs.into_iter().map(Step::Enter));
+
+        // Every valid file here makes this a SourceDir before any orphan is placed
+        let excluded: Vec<bool> = files
+            .iter()
+            .map(|f| is_excluded(f, &self.config, Some(&self.cache)))
+            .collect();
+        for (file, _) in files.iter().zip(&excluded).filter(|(_, e)| !**e) {
+            if let Some(parent) = file.parent() {
+                self.source_dirs.insert(parent.to_path_buf());
+            }
+        }
+
+        for (file, excluded) in files.into_iter().zip(excluded) {
+            if excluded {
+                self.emit(file, None);
+            } else if let Some(found) =
+                find_marker_root(&file, &self.config, Some(&self.marker_cache))
+            {
+                let info = RootInfo::marker(found, &self.config);
+                self.emit(file, Some(info));
+            } else {
+                self.place_orphan(file);
+            }
+        }
+    }
+
+    /// Emit an orphan whose orphanage is final, or park it until it can be
+    fn place_orphan(&mut self, file: PathBuf) {
+        let orphanage = find_orphanage(&file, &self.source_dirs, &*self.config.filesystem);
+        match orphanage.parent() {
+            Some(parent) if orphanage != self.start && parent != orphanage => {
+                self.pending
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(file);
+            }
+            _ => self.emit_orphan(file, orphanage),
+        }
+    }
+
+    fn leave(&mut self, dir: &Path) {
+        for file in self.pending.remove(dir).unwrap_or_default() {
+            let orphanage = find_orphanage(&file, &self.source_dirs, &*self.config.filesystem);
+            if orphanage.parent() == Some(dir) {
+                self.emit_orphan(file, orphanage);
+            } else {
+                self.place_orphan(file);
+            }
+        }
+    }
+
+    fn emit_orphan(&mut self, file: PathBuf, orphanage: PathBuf) {
+        let info = RootInfo::new(orphanage, Vec::new(), DetectionCase::Orphanage);
+        self.emit(file, Some(info));
+    }
+
+    fn emit(&mut self, file: PathBuf, info: Option<RootInfo>) {
+        self.ready.push_back(TraversalResult {
+            file,
+            root: info.as_ref().map(|i| i.root.clone()),
+            info,
+        });
+    }
+}
+
+impl Iterator for TraversalIter<'_> {
+    type Item = TraversalResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.ready.pop_front() {
+                return Some(result);
+            }
+            match self.steps.pop() {
+                Some(Step::Enter(job)) => self.enter(job),
+                Some(Step::Leave(dir)) => self.leave(&dir),
+                None => {
+                    // Only reachable if an orphanage's parent was never walked
+                    let pending: Vec<PathBuf> = self.pending.drain().flat_map(|(_, f)| f).collect();
+                    if pending.is_empty() {
+                        return None;
+                    }
+                    for file in pending {
+                        let orphanage =
+                            find_orphanage(&file, &self.source_dirs, &*self.config.filesystem);
+                        self.emit_orphan(file, orphanage);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse_and_detect;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn touch(root: &Path, path: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, "").unwrap();
+    }
+
+    #[test]
+    fn test_stream_matches_collected_traversal() {
+        let temp = TempDir::new().unwrap();
+        for path in [
+            "project/Cargo.toml",
+            "project/src/main.rs",
+            "flask-api/app/models/user.py",
+            "flask-api/migrations/env.py",
+            "flask-api/tests/test_user.py",
+            "web2text/main.py",
+            "web2text/app/api/model/user.py",
+            "lonely/deep/nested/file.py",
+            "node_modules/pkg/index.js",
+        ] {
+            touch(temp.path(), path);
+        }
+
+        let config = Config::default();
+        let options = TraversalOptions::default();
+        let mut streamed: Vec<TraversalResult> =
+            traverse_iter(temp.path(), &config, &options).collect();
+        streamed.sort_by(|a, b| a.file.cmp(&b.file));
+
+        assert_eq!(
+            streamed,
+            traverse_and_detect(temp.path(), &config, &options)
+        );
+    }
+
+    #[test]
+    fn test_marker_results_stream_before_walk_ends() {
+        let temp = TempDir::new().unwrap();
+        touch(temp.path(), "a/Cargo.toml");
+        touch(temp.path(), "a/src/lib.rs");
+        touch(temp.path(), "b/orphan/x.py");
+        touch(temp.path(), "b/orphan/y.py");
+
+        let config = Config::default();
+        let options = TraversalOptions::default().with_extensions(&["rs", "py"]);
+        let mut iter = traverse_iter(temp.path(), &config, &options);
+
+        // `a/` sorts first: its result is out before `b/` is read
+        let first = iter.next().unwrap();
+        assert_eq!(first.root, Some(temp.path().join("a")));
+        assert!(iter.pending.is_empty());
+        assert!(!iter.source_dirs.contains(&temp.path().join("b/orphan")));
+
+        let rest: Vec<TraversalResult> = iter.collect();
+        assert_eq!(rest.len(), 2);
+        assert!(rest
+            .iter()
+            .all(|r| r.root == Some(temp.path().join("b/orphan"))));
+    }
+}
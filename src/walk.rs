@@ -0,0 +1,300 @@
+//! Parallel directory walking and order-preserving parallel map.
+//!
+//! Workers share a stack of pending directories: each worker pops a
+//! directory, reads it, pushes its subdirectories back and keeps the files it
+//! found. Idle workers wait until new directories arrive or every worker is
+//! idle, which means the walk is complete. Results are sorted afterwards, so
+//! output does not depend on scheduling.
+
+use crate::ignore::IgnoreStack;
+use crate::{Config, TraversalOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// A directory waiting to be read
+pub struct DirJob {
+    pub dir: PathBuf,
+    depth: usize,
+    /// Ignore rules in effect for the directory's entries (before its own files)
+    ignores: Option<IgnoreStack>,
+}
+
+impl DirJob {
+    /// The traversal start directory
+    pub fn start(start: &Path, config: &Config, options: &TraversalOptions) -> Self {
+        Self {
+            dir: start.to_path_buf(),
+            depth: 0,
+            ignores: options.respect_ignore_files.then(|| {
+                IgnoreStack::for_start(
+                    &*config.filesystem,
+                    start,
+                    config.case_insensitive,
+                    options.global_excludes_file.as_deref(),
+                )
+            }),
+        }
+    }
+}
+
+#[derive(Default)]
+struct WalkState {
+    pending: Vec<DirJob>,
+    /// Workers currently reading a directory
+    active: usize,
+}
+
+/// A worker's claim on [`WalkState::active`]. Dropping it hands back the
+/// subdirectories found and wakes the idle workers, also when reading the
+/// directory panicked, so they can finish instead of waiting forever.
+struct ActiveJob<'a> {
+    state: &'a Mutex<WalkState>,
+    ready: &'a Condvar,
+    subdirs: Vec<DirJob>,
+}
+
+impl Drop for ActiveJob<'_> {
+    fn drop(&mut self) {
+        let mut guard = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        guard.pending.append(&mut self.subdirs);
+        guard.active -= 1;
+        drop(guard);
+        self.ready.notify_all();
+    }
+}
+
+/// Collect the files below `start` that traversal should consider, sorted
+pub fn collect_files(start: &Path, config: &Config, options: &TraversalOptions) -> Vec<PathBuf> {
+    let state = Mutex::new(WalkState {
+        pending: vec![DirJob::start(start, config, options)],
+        active: 0,
+    });
+    let ready = Condvar::new();
+
+    let threads = options.thread_count();
+    let mut files = if threads == 1 {
+        walk_worker(&state, &ready, config, options)
+    } else {
+        std::thread::scope(|scope| {
+            // Spawn every worker before joining any
+            #[allow(clippy::needless_collect)]
+            let workers: Vec<_> = (0..threads)
+                .map(|_| scope.spawn(|| walk_worker(&state, &ready, config, options)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("traversal worker panicked"))
+                .collect()
+        })
+    };
+
+    files.sort();
+    files
+}
+
+fn walk_worker(
+    state: &Mutex<WalkState>,
+    ready: &Condvar,
+    config: &Config,
+    options: &TraversalOptions,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    loop {
+        let job = {
+            let Ok(mut guard) = state.lock() else {
+                break;
+            };
+            loop {
+                if let Some(job) = guard.pending.pop() {
+                    guard.active += 1;
+                    break Some(job);
+                }
+                if guard.active == 0 {
+                    break None;
+                }
+                match ready.wait(guard) {
+                    Ok(next) => guard = next,
+                    Err(_) => return files,
+                }
+            }
+        };
+        let Some(job) = job else {
+            // Walk complete: wake the other idle workers so they exit too
+            ready.notify_all();
+            break;
+        };
+
+        let mut active = ActiveJob {
+            state,
+            ready,
+            subdirs: Vec::new(),
+        };
+        active.subdirs = scan_dir(job, config, options, &mut files);
+    }
+
+    files
+}
+
+/// Read one directory: push matching files, return subdirectories to visit
+pub fn scan_dir(
+    job: DirJob,
+    config: &Config,
+    options: &TraversalOptions,
+    files: &mut Vec<PathBuf>,
+) -> Vec<DirJob> {
+    let mut subdirs = Vec::new();
+
+    // Check max depth
+    if options.max_depth.is_some_and(|max| job.depth > max) {
+        return subdirs;
+    }
+
+    // Don't descend into exclusion zones
+    if config.is_exclusion_dir(&job.dir) {
+        return subdirs;
+    }
+
+    let fs = &*config.filesystem;
+    let Ok(entries) = fs.read_dir(&job.dir) else {
+        return subdirs; // Skip unreadable directories
+    };
+
+    // Ignore files in this directory apply to everything below it
+    let ignores = job.ignores.map(|stack| stack.enter(fs, &job.dir));
+
+    for path in entries {
+        let is_dir = fs.is_dir(&path);
+        if ignores
+            .as_ref()
+            .is_some_and(|stack| stack.is_ignored(&path, is_dir))
+        {
+            continue;
+        }
+
+        if is_dir {
+            subdirs.push(DirJob {
+                dir: path,
+                depth: job.depth + 1,
+                ignores: ignores.clone(),
+            });
+        } else if fs.is_file(&path) && options.matches_extension(&path) {
+            files.push(path);
+        }
+    }
+
+    subdirs
+}
+
+/// Map `f` over `items` on up to `threads` threads, keeping the input order
+pub fn parallel_map<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if threads <= 1 || items.len() < 2 {
+        return items.iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let f = &f;
+        // Spawn every chunk before joining any
+        #[allow(clippy::needless_collect)]
+        let chunks: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.join().expect("parallel_map worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileSystem, MemoryFs};
+    use std::{fs, io};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parallel_walk_matches_single_threaded() {
+        let temp = TempDir::new().unwrap();
+        for i in 0..20 {
+            for j in 0..5 {
+                let dir = temp.path().join(format!("d{i}/e{j}"));
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(dir.join("f.rs"), "").unwrap();
+            }
+        }
+        fs::create_dir_all(temp.path().join("node_modules/x")).unwrap();
+        fs::write(temp.path().join("node_modules/x/y.rs"), "").unwrap();
+
+        let config = Config::default();
+        let single = collect_files(
+            temp.path(),
+            &config,
+            &TraversalOptions::default().with_threads(1),
+        );
+        let parallel = collect_files(
+            temp.path(),
+            &config,
+            &TraversalOptions::default().with_threads(8),
+        );
+        assert_eq!(single.len(), 100);
+        assert_eq!(single, parallel);
+    }
+
+    /// Filesystem whose `read_dir` panics on one directory
+    #[derive(Debug)]
+    struct PanickyFs(MemoryFs);
+
+    impl FileSystem for PanickyFs {
+        fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+            assert!(!dir.ends_with("bad"), "cannot read {}", dir.display());
+            self.0.read_dir(dir)
+        }
+        fn is_dir(&self, path: &Path) -> bool {
+            self.0.is_dir(path)
+        }
+        fn is_file(&self, path: &Path) -> bool {
+            self.0.is_file(path)
+        }
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            self.0.canonicalize(path)
+        }
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.0.read_to_string(path)
+        }
+    }
+
+    #[test]
+    fn test_worker_panic_propagates() {
+        let mut fs = MemoryFs::new();
+        for i in 0..20 {
+            fs.add_file(format!("/code/d{i}/f.rs"), "");
+        }
+        fs.add_file("/code/d7/bad/g.rs", "");
+        let config = Config::default().with_filesystem(PanickyFs(fs));
+
+        // Ends with the panic instead of leaving the other workers waiting
+        let walk = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            collect_files(
+                Path::new("/code"),
+                &config,
+                &TraversalOptions::default().with_threads(4),
+            )
+        }));
+        assert!(walk.is_err());
+    }
+
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        let doubled = parallel_map(&items, 7, |i| i * 2);
+        assert_eq!(doubled, items.iter().map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
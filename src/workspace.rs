@@ -0,0 +1,346 @@
+//! Workspace-aware root resolution.
+//!
+//! With [`Config::resolve_workspaces`] enabled, a marker root that is a member
+//! of a workspace also reports the enclosing workspace root, so tooling can
+//! work at either level:
+//!
+//! ```text
+//! repo/
+//! ├── Cargo.toml            ← [workspace] members = ["crates/*"]
+//! └── crates/
+//!     └── core/
+//!         ├── Cargo.toml    ← member root
+//!         └── src/lib.rs    → root: repo/crates/core, workspace: repo/
+//! ```
+//!
+//! Each ecosystem module only knows how to read its own workspace definition
+//! and package metadata; membership checks and member listing are shared.
+
+mod cargo;
+mod dotnet;
+mod go;
+mod js;
+mod jvm;
+mod python;
+
+use crate::filesystem::normalize_lexically;
+use crate::glob::{is_glob, Glob};
+use crate::{ancestors_within, Config, FileSystem};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Build tool that defines a workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceKind {
+    /// Cargo workspace (`[workspace]` table in `Cargo.toml`)
+    Cargo,
+    /// npm workspaces (`workspaces` in `package.json`)
+    Npm,
+    /// Yarn workspaces (`workspaces` in `package.json` with a Yarn lockfile or config)
+    Yarn,
+    /// pnpm workspace (`pnpm-workspace.yaml`)
+    Pnpm,
+    /// Lerna monorepo (`lerna.json`)
+    Lerna,
+    /// Nx workspace (`nx.json`)
+    Nx,
+    /// uv workspace (`[tool.uv.workspace]` in `pyproject.toml`)
+    Uv,
+    /// Hatch workspace (`workspace.members` of a `[tool.hatch.envs.*]` table)
+    Hatch,
+    /// Poetry project depending on subprojects by path
+    Poetry,
+    /// Go workspace (`use` directives in `go.work`)
+    Go,
+    /// Gradle multi-project build (`include`s in `settings.gradle(.kts)`)
+    Gradle,
+    /// Maven reactor (`<modules>` in an aggregator `pom.xml`)
+    Maven,
+    /// .NET solution (projects listed in a `*.sln` / `*.slnx` file)
+    Solution,
+}
+
+/// Workspace enclosing a project root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceInfo {
+    /// The workspace root directory
+    pub root: PathBuf,
+    /// Build tool that defines the workspace
+    pub kind: WorkspaceKind,
+}
+
+/// Package metadata read from the manifest at a project root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageInfo {
+    /// Published name: crate, npm package, Python distribution, Go module path,
+    /// Maven `artifactId`, Gradle root project name or .NET project name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Directory of the importable top-level package (Python), if found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_dir: Option<PathBuf>,
+}
+
+/// A workspace root together with its member roots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Workspace {
+    /// The workspace root directory
+    pub root: PathBuf,
+    /// Build tool that defines the workspace
+    pub kind: WorkspaceKind,
+    /// Member project roots, sorted
+    pub members: Vec<PathBuf>,
+}
+
+/// Workspace definition read from the files in a candidate root
+struct Definition {
+    kind: WorkspaceKind,
+    /// Member globs, relative to the workspace root
+    include: Vec<String>,
+    /// Globs removing members selected by `include`
+    exclude: Vec<String>,
+    /// Files a member directory must contain (names or name globs)
+    member_manifests: &'static [&'static str],
+}
+
+impl Definition {
+    /// Build a definition from patterns where a leading `!` negates
+    fn from_patterns(
+        kind: WorkspaceKind,
+        patterns: Vec<String>,
+        member_manifests: &'static [&'static str],
+    ) -> Self {
+        let (exclude, include): (Vec<String>, Vec<String>) =
+            patterns.into_iter().partition(|p| p.starts_with('!'));
+        Self {
+            kind,
+            include,
+            exclude: exclude
+                .into_iter()
+                .map(|p| p.trim_start_matches('!').to_string())
+                .collect(),
+            member_manifests,
+        }
+    }
+
+    fn is_member(&self, root: &Path, dir: &Path) -> bool {
+        let Ok(relative) = dir.strip_prefix(root) else {
+            return false;
+        };
+        let components: Vec<&str> = relative
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+        if components.is_empty() {
+            return false;
+        }
+
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|p| Glob::new(p, false).matches_components(&components))
+        };
+        matches(&self.include) && !matches(&self.exclude)
+    }
+
+    fn has_member_manifest(&self, fs: &dyn FileSystem, dir: &Path) -> bool {
+        has_manifest(fs, dir, self.member_manifests)
+    }
+
+    /// Deepest directory level any `include` pattern can reach (None with `**`)
+    fn max_depth(&self) -> Option<usize> {
+        self.include.iter().try_fold(0, |deepest, pattern| {
+            let segments: Vec<&str> = pattern
+                .split('/')
+                .filter(|s| !s.is_empty() && *s != ".")
+                .collect();
+            if segments.contains(&"**") {
+                None
+            } else {
+                Some(deepest.max(segments.len()))
+            }
+        })
+    }
+
+    fn members(&self, root: &Path, config: &Config) -> Vec<PathBuf> {
+        let mut walk = MemberWalk {
+            definition: self,
+            root,
+            config,
+            max_depth: self.max_depth(),
+            visited: HashSet::new(),
+            members: Vec::new(),
+        };
+        walk.visit(root, 1);
+        walk.members.sort();
+        walk.members
+    }
+}
+
+/// Recursive search for the member directories of one definition
+struct MemberWalk<'a> {
+    definition: &'a Definition,
+    root: &'a Path,
+    config: &'a Config,
+    max_depth: Option<usize>,
+    /// Canonical directories already read, so symlink cycles end
+    visited: HashSet<PathBuf>,
+    members: Vec<PathBuf>,
+}
+
+impl MemberWalk<'_> {
+    fn visit(&mut self, dir: &Path, depth: usize) {
+        if self.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        let fs = &*self.config.filesystem;
+        let canonical = fs.canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        if !self.visited.insert(canonical) {
+            return;
+        }
+        let Ok(entries) = fs.read_dir(dir) else {
+            return;
+        };
+
+        for path in entries {
+            if !fs.is_dir(&path)
+                || path.file_name().is_some_and(|name| name == ".git")
+                || self.config.is_exclusion_dir(&path)
+            {
+                continue;
+            }
+
+            if self.definition.is_member(self.root, &path)
+                && self.definition.has_member_manifest(fs, &path)
+            {
+                self.members.push(path.clone());
+            }
+            self.visit(&path, depth + 1);
+        }
+    }
+}
+
+/// Find the workspace that `member_root` (a marker root) belongs to.
+///
+/// The member root itself counts when it is also the workspace root.
+/// Returns `None` if the directory is not part of any recognised workspace.
+#[must_use]
+pub fn find_workspace(member_root: &Path, config: &Config) -> Option<WorkspaceInfo> {
+    let fs = &*config.filesystem;
+    if let Some(info) = cargo::explicit_workspace(fs, member_root) {
+        return Some(info);
+    }
+
+    for resolver in RESOLVERS {
+        if !has_manifest(fs, member_root, resolver.manifests) {
+            continue;
+        }
+
+        for dir in ancestors_within(member_root, config) {
+            let Some(definition) = (resolver.definition)(fs, dir) else {
+                continue;
+            };
+
+            // Like the build tools themselves, the first workspace found decides membership
+            let is_member = definition.is_member(dir, member_root)
+                && definition.has_member_manifest(fs, member_root);
+            if dir == member_root || is_member {
+                return Some(WorkspaceInfo {
+                    root: dir.to_path_buf(),
+                    kind: definition.kind,
+                });
+            }
+            break;
+        }
+    }
+
+    None
+}
+
+/// Load the workspace rooted at `root`, listing its members.
+///
+/// Returns `None` if `root` does not define a recognised workspace.
+#[must_use]
+pub fn load_workspace(root: &Path, config: &Config) -> Option<Workspace> {
+    let definition = RESOLVERS
+        .iter()
+        .find_map(|r| (r.definition)(&*config.filesystem, root))?;
+    Some(Workspace {
+        root: root.to_path_buf(),
+        kind: definition.kind,
+        members: definition.members(root, config),
+    })
+}
+
+/// Read the package metadata of the project rooted at `root`.
+///
+/// Ecosystems are tried in the order of [`crate::ProjectKind`] precedence;
+/// returns `None` if no manifest there names a package.
+#[must_use]
+pub fn find_package(root: &Path, config: &Config) -> Option<PackageInfo> {
+    RESOLVERS
+        .iter()
+        .find_map(|r| (r.package)(&*config.filesystem, root))
+}
+
+/// Whether `dir` contains one of the `manifests`, given as names or name globs
+fn has_manifest(fs: &dyn FileSystem, dir: &Path, manifests: &[&str]) -> bool {
+    manifests.iter().any(|manifest| {
+        if !is_glob(manifest) {
+            return fs.is_file(&dir.join(manifest));
+        }
+        let glob = Glob::new(manifest, false);
+        fs.read_dir(dir).is_ok_and(|entries| {
+            entries.iter().any(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| glob.matches_components(&[name]))
+                    && fs.is_file(path)
+            })
+        })
+    })
+}
+
+/// Per-ecosystem hooks: which member manifests it can apply to, how to read a
+/// workspace definition from a candidate root, and how to read a root's
+/// package metadata
+struct Resolver {
+    manifests: &'static [&'static str],
+    definition: fn(&dyn FileSystem, &Path) -> Option<Definition>,
+    package: fn(&dyn FileSystem, &Path) -> Option<PackageInfo>,
+}
+
+const RESOLVERS: &[Resolver] = &[
+    Resolver {
+        manifests: cargo::MEMBER_MANIFESTS,
+        definition: cargo::definition,
+        package: cargo::package,
+    },
+    Resolver {
+        manifests: go::MEMBER_MANIFESTS,
+        definition: go::definition,
+        package: go::package,
+    },
+    Resolver {
+        manifests: jvm::MEMBER_MANIFESTS,
+        definition: jvm::definition,
+        package: jvm::package,
+    },
+    Resolver {
+        manifests: dotnet::MEMBER_MANIFESTS,
+        definition: dotnet::definition,
+        package: dotnet::package,
+    },
+    Resolver {
+        manifests: python::MEMBER_MANIFESTS,
+        definition: python::definition,
+        package: python::package,
+    },
+    Resolver {
+        manifests: js::ANY_MEMBER_MANIFESTS,
+        definition: js::definition,
+        package: js::package,
+    },
+];
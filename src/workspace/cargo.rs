@@ -0,0 +1,143 @@
+//! Cargo workspaces: `[workspace]` tables with `members` / `exclude` globs,
+//! and explicit `package.workspace` paths.
+
+use super::{normalize_lexically, Definition, PackageInfo, WorkspaceInfo, WorkspaceKind};
+use crate::FileSystem;
+use std::path::Path;
+use toml::Value;
+
+pub(super) const MEMBER_MANIFESTS: &[&str] = &["Cargo.toml"];
+
+fn read_manifest(fs: &dyn FileSystem, dir: &Path) -> Option<Value> {
+    let contents = fs.read_to_string(&dir.join("Cargo.toml")).ok()?;
+    contents.parse().ok()
+}
+
+fn string_array(table: &Value, key: &str) -> Vec<String> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `[workspace]` table of the `Cargo.toml` in `dir`
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let manifest = read_manifest(fs, dir)?;
+    let workspace = manifest.get("workspace")?;
+
+    Some(Definition {
+        kind: WorkspaceKind::Cargo,
+        include: string_array(workspace, "members"),
+        exclude: string_array(workspace, "exclude"),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+/// `package.name` of the `Cargo.toml` in `dir`
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let manifest = read_manifest(fs, dir)?;
+    let name = manifest.get("package")?.get("name")?.as_str()?;
+    Some(PackageInfo {
+        name: Some(name.to_string()),
+        import_dir: None,
+    })
+}
+
+/// Workspace named by a `package.workspace` key, which overrides the upward search
+pub(super) fn explicit_workspace(fs: &dyn FileSystem, member_root: &Path) -> Option<WorkspaceInfo> {
+    let manifest = read_manifest(fs, member_root)?;
+    let path = manifest.get("package")?.get("workspace")?.as_str()?;
+
+    Some(WorkspaceInfo {
+        root: normalize_lexically(&member_root.join(path)),
+        kind: WorkspaceKind::Cargo,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::workspace::{find_workspace, load_workspace, WorkspaceKind};
+    use crate::Config;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    #[test]
+    fn test_member_of_virtual_workspace() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/scratch\"]\n",
+        );
+        write(
+            temp.path(),
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\n",
+        );
+        write(
+            temp.path(),
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"scratch\"\n",
+        );
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("crates/core"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Cargo);
+
+        // Excluded members and unlisted crates belong to no workspace
+        assert!(find_workspace(&temp.path().join("crates/scratch"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(workspace.members, vec![temp.path().join("crates/core")]);
+    }
+
+    #[test]
+    fn test_root_package_is_its_own_workspace() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "Cargo.toml",
+            "[package]\nname = \"app\"\n\n[workspace]\nmembers = [\"tools\"]\n",
+        );
+
+        let info = find_workspace(temp.path(), &Config::default()).unwrap();
+        assert_eq!(info.root, temp.path());
+    }
+
+    #[test]
+    fn test_explicit_package_workspace_key() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "Cargo.toml", "[workspace]\nmembers = []\n");
+        write(
+            temp.path(),
+            "nested/member/Cargo.toml",
+            "[package]\nname = \"member\"\nworkspace = \"../..\"\n",
+        );
+
+        let info = find_workspace(&temp.path().join("nested/member"), &Config::default()).unwrap();
+        assert_eq!(info.root, temp.path());
+    }
+
+    #[test]
+    fn test_standalone_crate_has_no_workspace() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "app/Cargo.toml", "[package]\nname = \"app\"\n");
+
+        assert!(find_workspace(&temp.path().join("app"), &Config::default()).is_none());
+    }
+}
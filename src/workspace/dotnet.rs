@@ -0,0 +1,186 @@
+//! .NET solutions: project entries of `*.sln` files (and the XML `*.slnx`
+//! format), and project names from `*.csproj` / `*.fsproj` files.
+//!
+//! A solution's directory is the workspace root of every project it lists.
+
+use super::{Definition, PackageInfo, WorkspaceKind};
+use crate::FileSystem;
+use std::path::{Path, PathBuf};
+
+pub(super) const MEMBER_MANIFESTS: &[&str] = &["*.csproj", "*.fsproj"];
+
+const SOLUTION_EXTENSIONS: &[&str] = &["sln", "slnx"];
+const PROJECT_EXTENSIONS: &[&str] = &["csproj", "fsproj"];
+
+/// Projects listed by the solution files in `dir`
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let solutions = files_with_extension(fs, dir, SOLUTION_EXTENSIONS);
+    if solutions.is_empty() {
+        return None;
+    }
+
+    let include = solutions
+        .iter()
+        .filter_map(|solution| {
+            let contents = fs.read_to_string(solution).ok()?;
+            let xml = solution.extension().is_some_and(|ext| ext == "slnx");
+            Some(if xml {
+                slnx_projects(&contents)
+            } else {
+                sln_projects(&contents)
+            })
+        })
+        .flatten()
+        .filter_map(|project| {
+            let project = project.replace('\\', "/");
+            let (dir, _) = project.rsplit_once('/')?;
+            let dir = dir.trim_start_matches("./");
+            (!dir.is_empty() && !dir.split('/').any(|part| part == "..")).then(|| dir.to_string())
+        })
+        .collect();
+
+    Some(Definition {
+        kind: WorkspaceKind::Solution,
+        include,
+        exclude: Vec::new(),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+/// `<PackageId>`, `<AssemblyName>` or file name of the project file in `dir`
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let project = files_with_extension(fs, dir, PROJECT_EXTENSIONS)
+        .into_iter()
+        .next()?;
+    let contents = fs.read_to_string(&project).unwrap_or_default();
+    let name = ["PackageId", "AssemblyName"]
+        .iter()
+        .find_map(|element| element_text(&contents, element))
+        .filter(|name| !name.contains("$("))
+        .or_else(|| Some(project.file_stem()?.to_str()?.to_string()))?;
+    Some(PackageInfo {
+        name: Some(name),
+        import_dir: None,
+    })
+}
+
+/// Files of `dir` with one of the extensions (any case), sorted
+fn files_with_extension(fs: &dyn FileSystem, dir: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs
+        .read_dir(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        })
+        .filter(|path| fs.is_file(path))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Project paths of `Project("{type}") = "Name", "path", "{guid}"` lines,
+/// leaving out solution folders
+fn sln_projects(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("Project("))
+        .filter_map(|line| {
+            let (_, values) = line.split_once('=')?;
+            let path = values.split(',').nth(1)?.trim().trim_matches('"');
+            is_project_file(path).then(|| path.to_string())
+        })
+        .collect()
+}
+
+/// `Path` attributes of `<Project>` elements in an XML solution
+fn slnx_projects(contents: &str) -> Vec<String> {
+    contents
+        .split("<Project ")
+        .skip(1)
+        .filter_map(|element| {
+            let element = element.split('>').next()?;
+            let (_, rest) = element.split_once("Path=\"")?;
+            let path = rest.split('"').next()?;
+            is_project_file(path).then(|| path.to_string())
+        })
+        .collect()
+}
+
+fn is_project_file(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, ext)| {
+        PROJECT_EXTENSIONS
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(ext))
+    })
+}
+
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{name}>");
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{name}>"))?;
+    Some(xml[start..end].trim().to_string()).filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_package, find_workspace, load_workspace};
+    use crate::Config;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    const SOLUTION: &str = r#"
+Microsoft Visual Studio Solution File, Format Version 12.00
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Api", "src\Api\Api.csproj", "{6C1E6B4B-0001-4F4B-9C55-6E2C6E1B0001}"
+EndProject
+Project("{F2A71F9B-5D33-465A-A702-920D77279786}") = "Core", "src\Core\Core.fsproj", "{6C1E6B4B-0002-4F4B-9C55-6E2C6E1B0002}"
+EndProject
+Project("{2150E333-8FDC-42A3-9474-1A3956D46DE8}") = "docs", "docs", "{6C1E6B4B-0003-4F4B-9C55-6E2C6E1B0003}"
+EndProject
+"#;
+
+    #[test]
+    fn test_solution_projects() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "Shop.sln", SOLUTION);
+        write(
+            temp.path(),
+            "src/Api/Api.csproj",
+            "<Project Sdk=\"Microsoft.NET.Sdk.Web\">\n  <PropertyGroup>\n    <AssemblyName>Shop.Api</AssemblyName>\n  </PropertyGroup>\n</Project>\n",
+        );
+        write(temp.path(), "src/Core/Core.fsproj", "<Project />");
+        write(temp.path(), "tools/Gen/Gen.csproj", "<Project />");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("src/Api"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Solution);
+        assert!(find_workspace(&temp.path().join("tools/Gen"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![temp.path().join("src/Api"), temp.path().join("src/Core")]
+        );
+
+        let package = find_package(&temp.path().join("src/Api"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("Shop.Api"));
+        let package = find_package(&temp.path().join("src/Core"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("Core"));
+    }
+
+    #[test]
+    fn test_slnx_projects() {
+        let slnx = "<Solution>\n  <Folder Name=\"/src/\">\n    <Project Path=\"src/App/App.csproj\" />\n  </Folder>\n</Solution>\n";
+        assert_eq!(slnx_projects(slnx), vec!["src/App/App.csproj".to_string()]);
+    }
+}
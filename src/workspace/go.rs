@@ -0,0 +1,158 @@
+//! Go workspaces: `use` directives in `go.work`, and module paths from the
+//! `module` directive of `go.mod`.
+//!
+//! Every `go.mod` starts a separate module, so a nested module is its own root
+//! even when it sits inside another module's tree.
+
+use super::{Definition, PackageInfo, WorkspaceKind};
+use crate::FileSystem;
+use std::path::Path;
+
+pub(super) const MEMBER_MANIFESTS: &[&str] = &["go.mod"];
+
+/// `use` directives of the `go.work` in `dir`
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let contents = fs.read_to_string(&dir.join("go.work")).ok()?;
+    let include = directive_values(&contents, "use")
+        .into_iter()
+        .map(|path| {
+            path.trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string()
+        })
+        .filter(|path| !path.split('/').any(|part| part == ".."))
+        .collect();
+
+    Some(Definition {
+        kind: WorkspaceKind::Go,
+        include,
+        exclude: Vec::new(),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+/// Module path of the `go.mod` in `dir`
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let contents = fs.read_to_string(&dir.join("go.mod")).ok()?;
+    let module = directive_values(&contents, "module").into_iter().next()?;
+    Some(PackageInfo {
+        name: Some(module),
+        import_dir: None,
+    })
+}
+
+/// Arguments of every `directive`, in single-line (`use ./a`) or block form
+/// (`use (` ... `)`), unquoted and without `//` comments
+fn directive_values(contents: &str, directive: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut in_block = false;
+
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else {
+                values.push(unquote(line));
+            }
+            continue;
+        }
+
+        let Some(rest) = line.strip_prefix(directive) else {
+            continue;
+        };
+        if !rest.starts_with(char::is_whitespace) && !rest.starts_with('(') {
+            continue;
+        }
+        let rest = rest.trim();
+        if rest == "(" {
+            in_block = true;
+        } else if !rest.is_empty() {
+            values.push(unquote(rest));
+        }
+    }
+
+    values
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '`')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_package, find_workspace, load_workspace};
+    use crate::Config;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    #[test]
+    fn test_go_work_members() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "go.work",
+            "go 1.22\n\nuse (\n\t./api // service\n\t./tools/gen\n)\nuse ./lib\n",
+        );
+        write(
+            temp.path(),
+            "api/go.mod",
+            "module example.com/api\n\ngo 1.22\n",
+        );
+        write(
+            temp.path(),
+            "tools/gen/go.mod",
+            "module \"example.com/gen\"\n",
+        );
+        write(temp.path(), "lib/go.mod", "module example.com/lib\n");
+        write(
+            temp.path(),
+            "scratch/go.mod",
+            "module example.com/scratch\n",
+        );
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("tools/gen"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Go);
+        assert!(find_workspace(&temp.path().join("scratch"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![
+                temp.path().join("api"),
+                temp.path().join("lib"),
+                temp.path().join("tools/gen"),
+            ]
+        );
+
+        let package = find_package(&temp.path().join("tools/gen"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("example.com/gen"));
+    }
+
+    #[test]
+    fn test_directive_values() {
+        let go_mod =
+            "module example.com/m // the module\n\nrequire (\n\tgolang.org/x/mod v0.17.0\n)\n";
+        assert_eq!(
+            directive_values(go_mod, "module"),
+            vec!["example.com/m".to_string()]
+        );
+        assert!(directive_values(go_mod, "use").is_empty());
+    }
+}
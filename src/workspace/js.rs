@@ -0,0 +1,316 @@
+//! JavaScript monorepos: pnpm (`pnpm-workspace.yaml`), npm / Yarn
+//! (`workspaces` in `package.json`), Lerna (`lerna.json`) and Nx (`nx.json`).
+//!
+//! When several are present, the package manager's own definition wins, in the
+//! order pnpm, npm / Yarn, Lerna, Nx.
+
+use super::{Definition, PackageInfo, WorkspaceKind};
+use crate::FileSystem;
+use serde_json::Value;
+use std::path::Path;
+
+const MEMBER_MANIFESTS: &[&str] = &["package.json"];
+
+/// Nx projects are declared by either file
+const NX_MEMBER_MANIFESTS: &[&str] = &["package.json", "project.json"];
+
+/// Member manifests of any JavaScript workspace kind
+pub(super) const ANY_MEMBER_MANIFESTS: &[&str] = NX_MEMBER_MANIFESTS;
+
+/// Lerna's default when `lerna.json` has no `packages`
+const LERNA_DEFAULT_PACKAGES: &[&str] = &["packages/*"];
+
+const YARN_FILES: &[&str] = &["yarn.lock", ".yarnrc.yml", ".yarnrc"];
+
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    pnpm(fs, dir)
+        .or_else(|| package_json(fs, dir))
+        .or_else(|| lerna(fs, dir))
+        .or_else(|| nx(fs, dir))
+}
+
+/// `name` of the `package.json` in `dir`
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let manifest = read_json(fs, &dir.join("package.json"))?;
+    let name = manifest.get("name")?.as_str()?;
+    Some(PackageInfo {
+        name: Some(name.to_string()),
+        import_dir: None,
+    })
+}
+
+fn read_json(fs: &dyn FileSystem, path: &Path) -> Option<Value> {
+    let contents = fs.read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn string_array(value: &Value) -> Option<Vec<String>> {
+    value.as_array().map(|items| {
+        items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect()
+    })
+}
+
+fn pnpm(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let contents = fs.read_to_string(&dir.join("pnpm-workspace.yaml")).ok()?;
+    Some(Definition::from_patterns(
+        WorkspaceKind::Pnpm,
+        parse_pnpm_packages(&contents),
+        MEMBER_MANIFESTS,
+    ))
+}
+
+fn package_json(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let manifest = read_json(fs, &dir.join("package.json"))?;
+    let workspaces = manifest.get("workspaces")?;
+
+    // Either `"workspaces": [...]` or Yarn's `"workspaces": { "packages": [...] }`
+    let patterns =
+        string_array(workspaces).or_else(|| workspaces.get("packages").and_then(string_array))?;
+
+    let is_yarn = YARN_FILES.iter().any(|f| fs.exists(&dir.join(f)))
+        || manifest
+            .get("packageManager")
+            .and_then(Value::as_str)
+            .is_some_and(|pm| pm.starts_with("yarn@"));
+    let kind = if is_yarn {
+        WorkspaceKind::Yarn
+    } else {
+        WorkspaceKind::Npm
+    };
+
+    Some(Definition::from_patterns(kind, patterns, MEMBER_MANIFESTS))
+}
+
+fn lerna(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let config = read_json(fs, &dir.join("lerna.json"))?;
+    let patterns = config
+        .get("packages")
+        .and_then(string_array)
+        .unwrap_or_else(|| {
+            LERNA_DEFAULT_PACKAGES
+                .iter()
+                .copied()
+                .map(String::from)
+                .collect()
+        });
+
+    Some(Definition::from_patterns(
+        WorkspaceKind::Lerna,
+        patterns,
+        MEMBER_MANIFESTS,
+    ))
+}
+
+fn nx(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    read_json(fs, &dir.join("nx.json"))?;
+
+    // Nx projects can live anywhere below the workspace root
+    Some(Definition::from_patterns(
+        WorkspaceKind::Nx,
+        vec!["**".to_string()],
+        NX_MEMBER_MANIFESTS,
+    ))
+}
+
+/// Extract the `packages` list from `pnpm-workspace.yaml`.
+///
+/// Handles the block form (`- 'packages/*'` items) and the flow form
+/// (`packages: ['a/*', 'b']`), which is all pnpm itself documents.
+fn parse_pnpm_packages(contents: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+
+    for line in contents.lines() {
+        let line = strip_comment(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let indented = line.starts_with(char::is_whitespace);
+        let trimmed = line.trim();
+
+        if !indented {
+            in_packages = false;
+            if let Some(rest) = trimmed.strip_prefix("packages:") {
+                let rest = rest.trim();
+                // Block items follow on the next lines only when nothing is inline
+                in_packages = rest.is_empty();
+                if let Some(flow) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+                    packages.extend(flow.split(',').map(unquote).filter(|p| !p.is_empty()));
+                }
+            }
+        } else if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                packages.push(unquote(item));
+            }
+        }
+    }
+
+    packages
+}
+
+fn strip_comment(line: &str) -> &str {
+    // `#` only starts a comment at the line start or after whitespace
+    let bytes = line.as_bytes();
+    (0..bytes.len())
+        .find(|&i| bytes[i] == b'#' && (i == 0 || bytes[i - 1].is_ascii_whitespace()))
+        .map_or(line, |i| &line[..i])
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches(|c| c == '\'' || c == '"')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_workspace, load_workspace};
+    use crate::Config;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    #[test]
+    fn test_npm_and_yarn_workspaces() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "package.json",
+            r#"{"name": "mono", "workspaces": ["packages/*"]}"#,
+        );
+        write(
+            temp.path(),
+            "packages/api/package.json",
+            r#"{"name": "api"}"#,
+        );
+        write(
+            temp.path(),
+            "packages/web/package.json",
+            r#"{"name": "web"}"#,
+        );
+        write(temp.path(), "packages/docs/README.md", "");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("packages/api"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Npm);
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![
+                temp.path().join("packages/api"),
+                temp.path().join("packages/web"),
+            ]
+        );
+
+        write(temp.path(), "yarn.lock", "");
+        let info = find_workspace(&temp.path().join("packages/web"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Yarn);
+    }
+
+    #[test]
+    fn test_yarn_object_form() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "package.json",
+            r#"{"workspaces": {"packages": ["apps/*"], "nohoist": []}}"#,
+        );
+        write(temp.path(), "apps/site/package.json", "{}");
+
+        let info = find_workspace(&temp.path().join("apps/site"), &Config::default()).unwrap();
+        assert_eq!(info.root, temp.path());
+    }
+
+    #[test]
+    fn test_pnpm_workspace_with_negation() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "pnpm-workspace.yaml",
+            "packages:\n  - 'packages/**'  # all packages\n  - \"!**/fixtures/**\"\n",
+        );
+        write(temp.path(), "package.json", r#"{"name": "mono"}"#);
+        write(temp.path(), "packages/a/package.json", "{}");
+        write(temp.path(), "packages/group/b/package.json", "{}");
+        write(temp.path(), "packages/a/fixtures/x/package.json", "{}");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("packages/group/b"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Pnpm);
+        assert!(find_workspace(&temp.path().join("packages/a/fixtures/x"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![
+                temp.path().join("packages/a"),
+                temp.path().join("packages/group/b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lerna_and_nx() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "lerna/lerna.json", r#"{"version": "1.0.0"}"#);
+        write(temp.path(), "lerna/packages/core/package.json", "{}");
+        write(temp.path(), "nx/nx.json", "{}");
+        write(temp.path(), "nx/libs/ui/package.json", "{}");
+        write(temp.path(), "nx/apps/shop/project.json", "{}");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("lerna/packages/core"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Lerna);
+        assert_eq!(info.root, temp.path().join("lerna"));
+
+        let info = find_workspace(&temp.path().join("nx/libs/ui"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Nx);
+
+        // Nx projects declared by `project.json` alone belong to the workspace too
+        let info = find_workspace(&temp.path().join("nx/apps/shop"), &config).unwrap();
+        assert_eq!(info.root, temp.path().join("nx"));
+
+        let workspace = load_workspace(&temp.path().join("nx"), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![
+                temp.path().join("nx/apps/shop"),
+                temp.path().join("nx/libs/ui"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nx_members_survive_symlink_cycles() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "nx.json", "{}");
+        write(temp.path(), "libs/ui/project.json", "{}");
+        std::os::unix::fs::symlink(temp.path(), temp.path().join("libs/ui/loop")).unwrap();
+
+        let workspace = load_workspace(temp.path(), &Config::default()).unwrap();
+        assert_eq!(workspace.members, vec![temp.path().join("libs/ui")]);
+    }
+
+    #[test]
+    fn test_parse_pnpm_flow_form() {
+        assert_eq!(
+            parse_pnpm_packages("packages: ['apps/*', \"libs/*\"]\nonlyBuiltDependencies: []\n"),
+            vec!["apps/*".to_string(), "libs/*".to_string()]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! JVM multi-module builds: Gradle `include`s in `settings.gradle(.kts)` and
+//! Maven `<modules>` in an aggregator `pom.xml`.
+//!
+//! The build root is reported as the workspace and its modules as members.
+//! Gradle wins when a directory has both.
+
+use super::{Definition, PackageInfo, WorkspaceKind};
+use crate::FileSystem;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub(super) const MEMBER_MANIFESTS: &[&str] = &["pom.xml", "build.gradle", "build.gradle.kts"];
+
+const GRADLE_SETTINGS: &[&str] = &["settings.gradle.kts", "settings.gradle"];
+const GRADLE_MEMBER_MANIFESTS: &[&str] = &["build.gradle", "build.gradle.kts"];
+const MAVEN_MEMBER_MANIFESTS: &[&str] = &["pom.xml"];
+
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    gradle(fs, dir).or_else(|| maven(fs, dir))
+}
+
+/// `rootProject.name` of a Gradle build, or the `artifactId` of a Maven project
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let name = read_settings(fs, dir)
+        .and_then(|settings| root_project_name(&settings))
+        .or_else(|| {
+            let pom = fs.read_to_string(&dir.join("pom.xml")).ok()?;
+            artifact_id(&pom)
+        })?;
+    Some(PackageInfo {
+        name: Some(name),
+        import_dir: None,
+    })
+}
+
+fn read_settings(fs: &dyn FileSystem, dir: &Path) -> Option<String> {
+    GRADLE_SETTINGS
+        .iter()
+        .find_map(|name| fs.read_to_string(&dir.join(name)).ok())
+        .map(|contents| {
+            strip_block_comments(&contents, "/*", "*/")
+                .lines()
+                .map(|line| line.split("//").next().unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+}
+
+fn gradle(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let settings = read_settings(fs, dir)?;
+    let project_dirs = project_dirs(&settings);
+    let include = gradle_includes(&settings)
+        .into_iter()
+        .map(|project| {
+            project_dirs.get(&project).cloned().unwrap_or_else(|| {
+                // `:libs:core` lives in `libs/core` unless `projectDir` moves it
+                project.trim_start_matches(':').replace(':', "/")
+            })
+        })
+        .map(|dir| {
+            dir.trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string()
+        })
+        .filter(|dir| !dir.is_empty() && !dir.split('/').any(|part| part == ".."))
+        .collect();
+
+    Some(Definition {
+        kind: WorkspaceKind::Gradle,
+        include,
+        exclude: Vec::new(),
+        member_manifests: GRADLE_MEMBER_MANIFESTS,
+    })
+}
+
+fn maven(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let pom = fs.read_to_string(&dir.join("pom.xml")).ok()?;
+    let pom = strip_block_comments(&pom, "<!--", "-->");
+    let include: Vec<String> = element_blocks(&pom, "modules")
+        .flat_map(|modules| element_blocks(modules, "module"))
+        .map(|module| {
+            let module = module.trim().trim_start_matches("./");
+            // A module may name its POM instead of its directory
+            let module = module.strip_suffix("pom.xml").unwrap_or(module);
+            module.trim_end_matches('/').to_string()
+        })
+        .filter(|module| !module.is_empty() && !module.split('/').any(|part| part == ".."))
+        .collect();
+
+    (!include.is_empty()).then(|| Definition {
+        kind: WorkspaceKind::Maven,
+        include,
+        exclude: Vec::new(),
+        member_manifests: MAVEN_MEMBER_MANIFESTS,
+    })
+}
+
+/// Project paths passed to `include`, in Groovy (`include 'a', ':b:c'`) or
+/// Kotlin (`include(":a", ":b:c")`) form
+fn gradle_includes(settings: &str) -> Vec<String> {
+    let mut projects = Vec::new();
+    let mut rest = settings;
+    while let Some(start) = rest.find("include") {
+        let before = rest[..start].chars().next_back();
+        let after = &rest[start + "include".len()..];
+        rest = after;
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+            || after.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+        {
+            continue;
+        }
+
+        let trimmed = after.trim_start_matches([' ', '\t']);
+        let arguments = trimmed.strip_prefix('(').map_or_else(
+            || groovy_arguments(trimmed),
+            |inner| inner.split(')').next().unwrap_or_default(),
+        );
+        projects.extend(quoted_strings(arguments));
+    }
+    projects
+}
+
+/// Groovy call arguments run to the end of the line, continued by a trailing comma
+fn groovy_arguments(text: &str) -> &str {
+    let end = text
+        .match_indices('\n')
+        .map(|(i, _)| i)
+        .find(|&i| !text[..i].trim_end().ends_with(','))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Directories assigned with `project(":a").projectDir = file("path")`
+fn project_dirs(settings: &str) -> HashMap<String, String> {
+    settings
+        .lines()
+        .filter(|line| line.contains(".projectDir"))
+        .filter_map(|line| {
+            let mut strings = quoted_strings(line).into_iter();
+            Some((strings.next()?, strings.next()?))
+        })
+        .collect()
+}
+
+fn root_project_name(settings: &str) -> Option<String> {
+    settings
+        .lines()
+        .find(|line| line.trim_start().starts_with("rootProject.name"))
+        .and_then(|line| quoted_strings(line).into_iter().next())
+}
+
+/// `<artifactId>` of the project itself, not of its `<parent>`
+fn artifact_id(pom: &str) -> Option<String> {
+    let pom = strip_block_comments(pom, "<!--", "-->");
+    let without_parent = match (pom.find("<parent>"), pom.find("</parent>")) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}", &pom[..start], &pom[end + "</parent>".len()..])
+        }
+        _ => pom,
+    };
+    let id = element_blocks(&without_parent, "artifactId")
+        .next()
+        .map(|id| id.trim().to_string());
+    id
+}
+
+/// Contents of each `<name>...</name>` element
+fn element_blocks<'a>(xml: &'a str, name: &str) -> impl Iterator<Item = &'a str> {
+    let open = format!("<{name}>");
+    let close = format!("</{name}>");
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let end = start + rest[start..].find(&close)?;
+        let block = &rest[start..end];
+        rest = &rest[end + close.len()..];
+        Some(block)
+    })
+}
+
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = text.char_indices();
+    while let Some((start, quote)) = chars.find(|(_, c)| *c == '"' || *c == '\'') {
+        let Some((end, _)) = chars.find(|(_, c)| *c == quote) else {
+            break;
+        };
+        strings.push(text[start + 1..end].to_string());
+    }
+    strings
+}
+
+fn strip_block_comments(text: &str, open: &str, close: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        out.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find(close)
+            .map_or("", |end| &rest[start + end + close.len()..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_package, find_workspace, load_workspace};
+    use crate::Config;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    #[test]
+    fn test_gradle_settings_includes() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "settings.gradle.kts",
+            "rootProject.name = \"shop\"\n\ninclude(\":app\", \":libs:core\")\n// include(\":old\")\ninclude(\":legacy\")\nproject(\":legacy\").projectDir = file(\"archive/legacy\")\n",
+        );
+        write(temp.path(), "build.gradle.kts", "");
+        write(temp.path(), "app/build.gradle.kts", "");
+        write(temp.path(), "libs/core/build.gradle", "");
+        write(temp.path(), "archive/legacy/build.gradle", "");
+        write(temp.path(), "old/build.gradle.kts", "");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("libs/core"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Gradle);
+        assert!(find_workspace(&temp.path().join("old"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![
+                temp.path().join("app"),
+                temp.path().join("archive/legacy"),
+                temp.path().join("libs/core"),
+            ]
+        );
+
+        let package = find_package(temp.path(), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("shop"));
+    }
+
+    #[test]
+    fn test_maven_modules() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "pom.xml",
+            "<project>\n  <artifactId>parent</artifactId>\n  <packaging>pom</packaging>\n  <modules>\n    <module>core</module>\n    <!-- <module>old</module> -->\n    <module>web/pom.xml</module>\n  </modules>\n</project>\n",
+        );
+        write(
+            temp.path(),
+            "core/pom.xml",
+            "<project>\n  <parent>\n    <artifactId>parent</artifactId>\n  </parent>\n  <artifactId>core</artifactId>\n</project>\n",
+        );
+        write(temp.path(), "web/pom.xml", "<project/>");
+        write(temp.path(), "old/pom.xml", "<project/>");
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("core"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Maven);
+        assert!(find_workspace(&temp.path().join("old"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(
+            workspace.members,
+            vec![temp.path().join("core"), temp.path().join("web")]
+        );
+
+        let package = find_package(&temp.path().join("core"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("core"));
+    }
+
+    #[test]
+    fn test_groovy_includes() {
+        let settings =
+            "include 'app',\n    'libs:core'\ninclude ':cli'\nincludeBuild 'build-logic'\n";
+        assert_eq!(
+            gradle_includes(settings),
+            vec![
+                "app".to_string(),
+                "libs:core".to_string(),
+                ":cli".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! Python projects: uv workspaces (`[tool.uv.workspace]`), Hatch workspaces
+//! (`workspace.members` of an environment), Poetry path dependencies, and the
+//! distribution name and import package of a root.
+//!
+//! Metadata comes from `pyproject.toml` (`[project]`, `[tool.poetry]`), then
+//! `setup.cfg`, then a literal `name="..."` argument in `setup.py`.
+
+use super::{Definition, PackageInfo, WorkspaceKind};
+use crate::FileSystem;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+pub(super) const MEMBER_MANIFESTS: &[&str] = &["pyproject.toml"];
+
+/// Directories searched for the import package when nothing configures one
+const DEFAULT_PACKAGE_BASES: &[&str] = &["src", ""];
+
+pub(super) fn definition(fs: &dyn FileSystem, dir: &Path) -> Option<Definition> {
+    let manifest = read_pyproject(fs, dir)?;
+    let tool = manifest.get("tool")?;
+    uv(tool).or_else(|| hatch(tool)).or_else(|| poetry(tool))
+}
+
+/// Distribution name and import package directory of the project in `dir`
+pub(super) fn package(fs: &dyn FileSystem, dir: &Path) -> Option<PackageInfo> {
+    let pyproject = read_pyproject(fs, dir);
+    let setup_cfg = fs.read_to_string(&dir.join("setup.cfg")).ok();
+
+    let name = pyproject
+        .as_ref()
+        .and_then(pyproject_name)
+        .or_else(|| {
+            setup_cfg
+                .as_deref()
+                .and_then(|c| ini_value(c, "metadata", "name"))
+        })
+        .or_else(|| {
+            let setup_py = fs.read_to_string(&dir.join("setup.py")).ok()?;
+            setup_py_name(&setup_py)
+        });
+
+    let configured = pyproject
+        .as_ref()
+        .and_then(configured_package)
+        .or_else(|| setup_cfg.as_deref().and_then(setup_cfg_package));
+    let import_dir = match configured {
+        Some(PackageLocation::Dir(path)) => Some(dir.join(path)),
+        Some(PackageLocation::Base(base)) => name
+            .as_deref()
+            .and_then(|name| find_import_dir(fs, dir, &[base.as_str()], name)),
+        None => name
+            .as_deref()
+            .and_then(|name| find_import_dir(fs, dir, DEFAULT_PACKAGE_BASES, name)),
+    };
+
+    if name.is_none() && import_dir.is_none() {
+        return None;
+    }
+    Some(PackageInfo { name, import_dir })
+}
+
+fn read_pyproject(fs: &dyn FileSystem, dir: &Path) -> Option<Value> {
+    let contents = fs.read_to_string(&dir.join("pyproject.toml")).ok()?;
+    contents.parse().ok()
+}
+
+fn string_array(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn uv(tool: &Value) -> Option<Definition> {
+    let workspace = tool.get("uv")?.get("workspace")?;
+    Some(Definition {
+        kind: WorkspaceKind::Uv,
+        include: string_array(workspace.get("members")),
+        exclude: string_array(workspace.get("exclude")),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+/// Members of every Hatch environment, given as paths or `{ path = ... }` tables
+fn hatch(tool: &Value) -> Option<Definition> {
+    let envs = tool.get("hatch")?.get("envs")?.as_table()?;
+    let mut include = Vec::new();
+    let mut found = false;
+    for members in envs
+        .values()
+        .filter_map(|env| env.get("workspace")?.get("members")?.as_array())
+    {
+        found = true;
+        include.extend(
+            members
+                .iter()
+                .filter_map(|m| m.as_str().or_else(|| m.get("path")?.as_str()))
+                .map(String::from),
+        );
+    }
+
+    found.then(|| Definition {
+        kind: WorkspaceKind::Hatch,
+        include,
+        exclude: Vec::new(),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+/// Path dependencies below the project, from the main, dev and group tables
+fn poetry(tool: &Value) -> Option<Definition> {
+    let poetry = tool.get("poetry")?;
+    let groups = poetry
+        .get("group")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|groups| groups.values().filter_map(|g| g.get("dependencies")));
+    let tables = [poetry.get("dependencies"), poetry.get("dev-dependencies")]
+        .into_iter()
+        .flatten()
+        .chain(groups)
+        .filter_map(Value::as_table);
+
+    let include: Vec<String> = tables
+        .flat_map(|deps| deps.values().filter_map(|dep| dep.get("path")?.as_str()))
+        .map(|path| path.trim_start_matches("./").trim_end_matches('/'))
+        .filter(|path| !path.is_empty() && !path.split('/').any(|part| part == ".."))
+        .map(String::from)
+        .collect();
+
+    (!include.is_empty()).then(|| Definition {
+        kind: WorkspaceKind::Poetry,
+        include,
+        exclude: Vec::new(),
+        member_manifests: MEMBER_MANIFESTS,
+    })
+}
+
+fn pyproject_name(manifest: &Value) -> Option<String> {
+    let name = manifest
+        .get("project")
+        .and_then(|p| p.get("name"))
+        .or_else(|| manifest.get("tool")?.get("poetry")?.get("name"))?;
+    name.as_str().map(String::from)
+}
+
+/// Where the build configuration puts the import package
+#[derive(Debug, PartialEq, Eq)]
+enum PackageLocation {
+    /// The package directory itself, relative to the root
+    Dir(String),
+    /// Directory containing top-level packages, relative to the root
+    Base(String),
+}
+
+/// Package location from Poetry, Hatch or setuptools settings in `pyproject.toml`
+fn configured_package(manifest: &Value) -> Option<PackageLocation> {
+    let tool = manifest.get("tool")?;
+
+    let poetry = tool
+        .get("poetry")
+        .and_then(|p| p.get("packages")?.as_array()?.first())
+        .and_then(|package| {
+            let include = package.get("include")?.as_str()?;
+            let from = package.get("from").and_then(Value::as_str).unwrap_or("");
+            Some(PackageLocation::Dir(join(from, include)))
+        });
+    let hatch = || {
+        let wheel = tool
+            .get("hatch")?
+            .get("build")?
+            .get("targets")?
+            .get("wheel")?;
+        let first = wheel.get("packages")?.as_array()?.first()?.as_str()?;
+        Some(PackageLocation::Dir(
+            first.trim_end_matches('/').to_string(),
+        ))
+    };
+    let setuptools = || {
+        let setuptools = tool.get("setuptools")?;
+        let package_dir = setuptools
+            .get("package-dir")
+            .and_then(|dirs| dirs.get("")?.as_str());
+        let find_where = || {
+            let where_ = setuptools.get("packages")?.get("find")?.get("where")?;
+            string_array(Some(where_)).into_iter().next()
+        };
+        package_dir
+            .map(String::from)
+            .or_else(find_where)
+            .map(PackageLocation::Base)
+    };
+
+    poetry.or_else(hatch).or_else(setuptools)
+}
+
+/// Package base from `[options] package_dir` or `[options.packages.find] where`
+fn setup_cfg_package(contents: &str) -> Option<PackageLocation> {
+    let package_dir = ini_value(contents, "options", "package_dir").and_then(|value| {
+        value.lines().find_map(|line| {
+            let (package, dir) = line.split_once('=')?;
+            package.trim().is_empty().then(|| dir.trim().to_string())
+        })
+    });
+    package_dir
+        .or_else(|| ini_value(contents, "options.packages.find", "where"))
+        .map(PackageLocation::Base)
+}
+
+/// Existing directory for `name` under one of `bases`: the normalized module
+/// name (`my-lib` → `my_lib`), or the namespace form (`acme.tools` → `acme/tools`)
+fn find_import_dir(
+    fs: &dyn FileSystem,
+    root: &Path,
+    bases: &[&str],
+    name: &str,
+) -> Option<PathBuf> {
+    let normalized = name.to_lowercase().replace(['-', '.'], "_");
+    let namespaced = name.to_lowercase().replace('.', "/").replace('-', "_");
+    bases.iter().find_map(|base| {
+        [&normalized, &namespaced]
+            .into_iter()
+            .map(|module| root.join(join(base, module)))
+            .find(|path| fs.is_dir(path))
+    })
+}
+
+fn join(base: &str, path: &str) -> String {
+    let base = base.trim_start_matches("./").trim_end_matches('/');
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    if base.is_empty() || base == "." {
+        path.to_string()
+    } else {
+        format!("{base}/{path}")
+    }
+}
+
+/// Value of `key` in `[section]` of an INI file, with continuation lines
+/// joined by newlines
+fn ini_value(contents: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    let mut value: Option<String> = None;
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+
+        let indented = line.starts_with(char::is_whitespace);
+        if let Some(value) = value.as_mut() {
+            if indented {
+                value.push('\n');
+                value.push_str(trimmed);
+                continue;
+            }
+            break;
+        }
+
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section && !indented {
+            let Some((k, v)) = trimmed.split_once(['=', ':']) else {
+                continue;
+            };
+            if k.trim() == key {
+                value = Some(v.trim().to_string());
+            }
+        }
+    }
+
+    value.map(|v| v.trim().to_string())
+}
+
+/// String literal passed as `name=` to a `setup(...)` call
+fn setup_py_name(contents: &str) -> Option<String> {
+    contents.match_indices("name").find_map(|(start, _)| {
+        let before = contents[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let rest = contents[start + "name".len()..].trim_start();
+        let rest = rest.strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let literal = &rest[1..];
+        literal.find(quote).map(|end| literal[..end].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_package, find_workspace, load_workspace};
+    use crate::Config;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(full, contents).unwrap();
+    }
+
+    #[test]
+    fn test_uv_workspace() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "pyproject.toml",
+            "[project]\nname = \"mono\"\n\n[tool.uv.workspace]\nmembers = [\"packages/*\"]\nexclude = [\"packages/legacy\"]\n",
+        );
+        write(
+            temp.path(),
+            "packages/core/pyproject.toml",
+            "[project]\nname = \"acme-core\"\n",
+        );
+        write(
+            temp.path(),
+            "packages/legacy/pyproject.toml",
+            "[project]\nname = \"legacy\"\n",
+        );
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("packages/core"), &config).unwrap();
+        assert_eq!(info.root, temp.path());
+        assert_eq!(info.kind, WorkspaceKind::Uv);
+        assert!(find_workspace(&temp.path().join("packages/legacy"), &config).is_none());
+
+        let workspace = load_workspace(temp.path(), &config).unwrap();
+        assert_eq!(workspace.members, vec![temp.path().join("packages/core")]);
+    }
+
+    #[test]
+    fn test_hatch_and_poetry_workspaces() {
+        let temp = TempDir::new().unwrap();
+        write(
+            temp.path(),
+            "hatch/pyproject.toml",
+            "[project]\nname = \"app\"\n\n[tool.hatch.envs.default]\nworkspace.members = [{ path = \"libs/util\" }]\n",
+        );
+        write(
+            temp.path(),
+            "hatch/libs/util/pyproject.toml",
+            "[project]\nname = \"util\"\n",
+        );
+        write(
+            temp.path(),
+            "poetry/pyproject.toml",
+            "[tool.poetry]\nname = \"app\"\n\n[tool.poetry.group.dev.dependencies]\nshared = { path = \"./shared\", develop = true }\n",
+        );
+        write(
+            temp.path(),
+            "poetry/shared/pyproject.toml",
+            "[tool.poetry]\nname = \"shared\"\n",
+        );
+
+        let config = Config::default();
+        let info = find_workspace(&temp.path().join("hatch/libs/util"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Hatch);
+        assert_eq!(info.root, temp.path().join("hatch"));
+
+        let info = find_workspace(&temp.path().join("poetry/shared"), &config).unwrap();
+        assert_eq!(info.kind, WorkspaceKind::Poetry);
+        assert_eq!(info.root, temp.path().join("poetry"));
+    }
+
+    #[test]
+    fn test_package_name_and_import_dir() {
+        let temp = TempDir::new().unwrap();
+        let config = Config::default();
+
+        // src layout found from the normalized distribution name
+        write(
+            temp.path(),
+            "uv/pyproject.toml",
+            "[project]\nname = \"Acme-Tools\"\n",
+        );
+        write(temp.path(), "uv/src/acme_tools/__init__.py", "");
+        let package = find_package(&temp.path().join("uv"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("Acme-Tools"));
+        assert_eq!(
+            package.import_dir,
+            Some(temp.path().join("uv/src/acme_tools"))
+        );
+
+        // Poetry's explicit `packages` wins over the name
+        write(
+            temp.path(),
+            "poetry/pyproject.toml",
+            "[tool.poetry]\nname = \"service\"\npackages = [{ include = \"svc\", from = \"lib\" }]\n",
+        );
+        let package = find_package(&temp.path().join("poetry"), &config).unwrap();
+        assert_eq!(package.import_dir, Some(temp.path().join("poetry/lib/svc")));
+
+        // setup.cfg with a package_dir mapping
+        write(
+            temp.path(),
+            "cfg/setup.cfg",
+            "[metadata]\nname = legacy.plugin\n\n[options]\npackage_dir =\n    =source\n",
+        );
+        write(temp.path(), "cfg/source/legacy/plugin/__init__.py", "");
+        let package = find_package(&temp.path().join("cfg"), &config).unwrap();
+        assert_eq!(package.name.as_deref(), Some("legacy.plugin"));
+        assert_eq!(
+            package.import_dir,
+            Some(temp.path().join("cfg/source/legacy/plugin"))
+        );
+    }
+
+    #[test]
+    fn test_setup_py_name() {
+        assert_eq!(
+            setup_py_name(
+                "from setuptools import setup\nsetup(\n    name = 'flat',\n    version='1')\n"
+            ),
+            Some("flat".to_string())
+        );
+        assert_eq!(setup_py_name("setup(package_name=\"x\")\n"), None);
+    }
+}